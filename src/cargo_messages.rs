@@ -0,0 +1,89 @@
+//! Cargo's `--message-format=json` output
+//!
+//! Complements the fingerprint log: `compiler-artifact` messages tell which
+//! units were actually built, but not why.
+
+use serde::Deserialize;
+
+use crate::rebuild_graph::PackageTarget;
+
+/// One line of cargo's JSON message stream. Only artifacts are of interest.
+#[derive(Deserialize)]
+#[serde(tag = "reason", rename_all = "kebab-case")]
+enum Message {
+    CompilerArtifact(Artifact),
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize)]
+struct Artifact {
+    package_id: String,
+    target: ArtifactTarget,
+    fresh: bool,
+}
+
+#[derive(Deserialize)]
+struct ArtifactTarget {
+    name: String,
+}
+
+/// The unit of a `compiler-artifact` message that was rebuilt, i.e. not
+/// `fresh`. `None` for fresh artifacts, other messages and non-JSON lines.
+#[must_use]
+pub fn parse_rebuilt_unit(line: &str) -> Option<PackageTarget> {
+    let Ok(Message::CompilerArtifact(artifact)) = serde_json::from_str(line) else {
+        return None;
+    };
+    if artifact.fresh {
+        return None;
+    }
+    let (name, version) = split_package_id(&artifact.package_id)?;
+    Some(PackageTarget::new(
+        format!("{name} v{version}"),
+        Some(artifact.target.name),
+    ))
+}
+
+// Name and version from a package id spec such as
+// "registry+https://github.com/rust-lang/crates.io-index#serde@1.0.200" or
+// "path+file:///src/lib-a#0.1.0", or the older "lib-a 0.1.0 (path+file:///src/lib-a)"
+fn split_package_id(package_id: &str) -> Option<(&str, &str)> {
+    let Some((source, fragment)) = package_id.rsplit_once('#') else {
+        let mut words = package_id.split_whitespace();
+        return words.next().zip(words.next());
+    };
+    fragment.split_once('@').or_else(|| {
+        let name = source.trim_end_matches('/').rsplit('/').next()?;
+        Some((name, fragment))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_units_that_were_not_fresh() {
+        let lines = [
+            r#"{"reason":"compiler-artifact","package_id":"path+file:///src/lib-a#0.1.0","manifest_path":"/src/lib-a/Cargo.toml","target":{"kind":["lib"],"crate_types":["lib"],"name":"lib_a","src_path":"/src/lib-a/src/lib.rs"},"profile":{},"features":[],"filenames":[],"executable":null,"fresh":false}"#,
+            r#"{"reason":"compiler-artifact","package_id":"registry+https://github.com/rust-lang/crates.io-index#serde@1.0.200","target":{"kind":["lib"],"name":"serde"},"fresh":true}"#,
+            r#"{"reason":"compiler-artifact","package_id":"libz-sys 1.1.23 (registry+https://github.com/rust-lang/crates.io-index)","target":{"kind":["custom-build"],"name":"build-script-build"},"fresh":false}"#,
+            r#"{"reason":"build-finished","success":true}"#,
+            "   Compiling lib-a v0.1.0",
+        ];
+
+        let rebuilt: Vec<_> = lines
+            .iter()
+            .filter_map(|line| parse_rebuilt_unit(line))
+            .collect();
+
+        assert_eq!(
+            rebuilt,
+            [
+                PackageTarget::new("lib-a v0.1.0", Some("lib_a".to_string())),
+                PackageTarget::new("libz-sys v1.1.23", Some("build-script-build".to_string())),
+            ]
+        );
+    }
+}
@@ -1,19 +1,211 @@
 use std::{
+    cmp::Reverse,
+    collections::BTreeSet,
     env,
-    io::{BufRead, BufReader},
-    path::PathBuf,
-    process::{ChildStderr, Command, Stdio},
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, IsTerminal, Write},
+    iter,
+    path::{Path, PathBuf},
+    process::{Child, ChildStderr, Command, Stdio},
+    sync::{
+        OnceLock,
+        mpsc::{self, RecvTimeoutError},
+    },
+    thread,
+    time::{Duration, Instant},
 };
 
-use clap::Parser;
+use clap::{CommandFactory, Parser, ValueEnum, error::ErrorKind};
 use log::{debug, info};
 
+#[cfg(feature = "tui")]
+use crate::tui;
 use crate::{
     AnalyzerError,
-    fingerprint_parser::parse_rebuild_entry,
-    rebuild_graph::{RebuildGraph, RebuildNode},
+    cargo_messages::parse_rebuilt_unit,
+    file_watcher::FileWatcher,
+    fingerprint_parser::{
+        ParseFailure, ParsedRebuildEntry, UnitHints, parse_compiled_package, parse_package_target,
+        parse_rebuild_entry, parse_rebuild_reason_verbose,
+    },
+    rebuild_analysis::{RebuildAnalysis, RebuildSummary},
+    rebuild_graph::{
+        PackageTarget, RebuildGraph, RebuildNode, RootCauseChain, TargetKind, TargetKinds,
+    },
+    rebuild_reason::{IconStyle, ReasonKind, RebuildReason, Severity},
+    rebuild_reporter::{
+        FrequencyReport, JsonStyle, ReasonHistogram, TextStyle, bottom_line, cascade_tree,
+        nodes_to_json, print_by_package, print_by_reason_kind, print_root_causes,
+        print_targets_by_package, print_trigger_delta, relocation_hint, targets_by_package,
+        to_frequency_list, to_json, to_junit, to_key_values, to_markdown, to_mermaid,
+        trees_to_json, write_csv, write_ndjson_event,
+    },
 };
 
+/// What one pass over cargo logs found
+#[derive(Default)]
+struct LogScan {
+    graph: RebuildGraph,
+    histogram: ReasonHistogram,
+    /// Whether any fingerprint output was logged at all
+    fingerprint_seen: bool,
+    /// Packages cargo reported compiling
+    compiled: BTreeSet<String>,
+    /// Packages built without a previous fingerprint
+    first_builds: BTreeSet<String>,
+    /// Trigger lines whose reason could not be parsed, kept for `--strict`
+    unparsed: Vec<String>,
+}
+
+/// `CARGO_LOG` for `--deep`, which also logs each unit's fingerprint file
+const DEEP_CARGO_LOG: &str = "cargo::core::compiler::fingerprint=debug";
+
+/// Quiet period after a file change before `--watch` analyzes again
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// How the analysis is printed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Root causes with explanations
+    #[default]
+    Text,
+    /// Root cause chains as JSON
+    Json,
+    /// Root causes as JSON trees, nesting each rebuild under its cause
+    JsonTree,
+    /// Mermaid flowchart of root causes and the packages they rebuilt
+    Mermaid,
+    /// One CSV row per rebuilt unit
+    Csv,
+    /// Markdown summary for issues and pull requests
+    Markdown,
+    /// Rebuild reasons ranked by how many units they rebuilt
+    Frequency,
+    /// One JSON object per rebuild trigger, printed as soon as it is parsed
+    Ndjson,
+    /// Every unique rebuilt unit as a flat JSON array
+    NodesJson,
+    /// One line of `key=value` pairs per root cause, for grep and awk
+    Kv,
+    /// `JUnit` XML with a test case per root cause, failing those matched by
+    /// `--fail-on`
+    Junit,
+}
+
+/// How the text output is organized
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum GroupBy {
+    /// Each root cause with the packages it rebuilt
+    #[default]
+    Root,
+    /// Each rebuilt package with the reasons of its targets
+    Package,
+    /// Rebuilt units under a header per reason kind
+    Reason,
+}
+
+/// When to colorize the text output
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Colorize when stdout is a terminal and `NO_COLOR` is not set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// Rebuild reasons that make the run fail
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum FailOn {
+    /// Any rebuild
+    Any,
+    /// Environment variable changes
+    Env,
+    /// Rebuilds caused by a dependency
+    Dep,
+    /// Rustflags, features, profile or target configuration changes
+    Config,
+}
+
+impl FailOn {
+    const fn matches(self, kind: ReasonKind) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Env => matches!(kind, ReasonKind::EnvVar),
+            Self::Dep => matches!(kind, ReasonKind::Dependency),
+            Self::Config => matches!(
+                kind,
+                ReasonKind::Rustflags
+                    | ReasonKind::Features
+                    | ReasonKind::Profile
+                    | ReasonKind::TargetConfig
+            ),
+        }
+    }
+}
+
+/// Reason kinds `--explain` describes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ExplainKind {
+    /// Environment variable changes
+    Env,
+    /// Rebuilds caused by a dependency
+    Dep,
+    Rustflags,
+    Features,
+    Profile,
+    /// Target configuration changes
+    Target,
+    /// Source file changes
+    File,
+}
+
+impl ExplainKind {
+    /// A typical reason of this kind, whose explanation serves as reference
+    fn example(self) -> RebuildReason {
+        match self {
+            Self::Env => RebuildReason::EnvVarChanged {
+                name: "CC".to_string(),
+                old_value: Some("gcc".to_string()),
+                new_value: Some("clang".to_string()),
+            },
+            Self::Dep => RebuildReason::UnitDependencyInfoChanged {
+                name: "libz_sys".to_string(),
+                old_fingerprint: String::new(),
+                new_fingerprint: String::new(),
+                context: None,
+            },
+            Self::Rustflags => RebuildReason::RustflagsChanged {
+                old: Vec::new(),
+                new: vec!["-C".to_string(), "target-cpu=native".to_string()],
+            },
+            Self::Features => RebuildReason::FeaturesChanged {
+                old: "default".to_string(),
+                new: "default,serde".to_string(),
+            },
+            Self::Profile => RebuildReason::ProfileConfigurationChanged,
+            Self::Target => RebuildReason::TargetConfigurationChanged,
+            Self::File => RebuildReason::FileChanged {
+                path: "src/lib.rs".to_string(),
+                reference: None,
+            },
+        }
+    }
+}
+
+impl ColorChoice {
+    fn enabled(self) -> bool {
+        match self {
+            Self::Auto => {
+                env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+                    && io::stdout().is_terminal()
+            }
+            Self::Always => true,
+            Self::Never => false,
+        }
+    }
+}
+
 #[derive(Parser, Debug)]
 #[command(author, version, about = "Analyze what causes cargo rebuilds", long_about = None)]
 pub struct Config {
@@ -23,24 +215,355 @@ pub struct Config {
     #[arg(short, long, help = "Verbose output")]
     verbose: bool,
 
-    #[arg(long, help = "Output analysis as JSON")]
+    #[arg(
+        short,
+        long,
+        help = "Only print the number of rebuilt units per kind of reason",
+        conflicts_with = "verbose"
+    )]
+    quiet: bool,
+
+    #[arg(
+        long,
+        help = "Output analysis as JSON (same as --format json, or the frequency report as JSON \
+                with --format frequency)"
+    )]
     json: bool,
 
+    #[arg(long, help = "Print JSON output on a single line instead of indented")]
+    compact: bool,
+
+    #[arg(
+        long,
+        help = "Print JSON output as a bare array of root causes without the versioned envelope \
+                (deprecated)"
+    )]
+    json_flat: bool,
+
+    #[arg(long, value_enum, help = "Output format", default_value_t)]
+    format: OutputFormat,
+
+    #[arg(long, value_enum, help = "When to use colors", default_value_t)]
+    color: ColorChoice,
+
+    #[arg(
+        long,
+        help = "Use ASCII tags like [ENV] instead of emoji in explanations"
+    )]
+    ascii: bool,
+
+    #[arg(
+        long,
+        help = "Leave suggestions out of explanations, showing only what changed"
+    )]
+    no_suggestions: bool,
+
+    #[arg(
+        long,
+        help = "List every package rebuilt by a root cause instead of collapsing long lists"
+    )]
+    expand: bool,
+
+    #[arg(
+        long,
+        help = "Show changed files with the absolute paths cargo logged instead of relative to \
+                the project"
+    )]
+    abs_paths: bool,
+
+    #[cfg(feature = "tui")]
+    #[arg(
+        long,
+        help = "Browse the root causes in an interactive terminal interface",
+        conflicts_with_all = ["quiet", "watch"]
+    )]
+    tui: bool,
+
     #[arg(long, help = "Cargo command to analyze", default_value = "check")]
     command: String,
 
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Build profile to analyze, passed to cargo as --profile NAME"
+    )]
+    profile: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "TRIPLE",
+        help = "Target triple to analyze, passed to cargo as --target TRIPLE"
+    )]
+    target: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Cargo binary to run [default: $CARGO, or cargo from PATH]"
+    )]
+    cargo_bin: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "SPEC",
+        help = "CARGO_LOG filter for the analyzed cargo run; it must log the fingerprint module \
+                at info level or finer",
+        default_value = "cargo::core::compiler::fingerprint=info"
+    )]
+    cargo_log: String,
+
+    #[arg(
+        long,
+        help = "Log fingerprints at debug level to attach each rebuilt unit's fingerprint file to \
+                dependency changes",
+        conflicts_with = "cargo_log"
+    )]
+    deep: bool,
+
+    #[arg(
+        long = "match",
+        value_name = "SUBSTRING",
+        help = "Also parse log lines containing SUBSTRING as rebuild triggers (repeatable), for \
+                logs of other cargo versions or forks"
+    )]
+    trigger_markers: Vec<String>,
+
+    #[arg(
+        long,
+        help = "Set only CARGO_LOG for the analyzed cargo run and leave RUST_LOG unset, which is \
+                faster and quieter than the default RUST_LOG=debug"
+    )]
+    no_default_log_override: bool,
+
+    #[arg(
+        long = "env",
+        value_name = "KEY=VALUE",
+        value_parser = parse_env_assignment,
+        help = "Set an environment variable for the cargo run (repeatable)"
+    )]
+    envs: Vec<(String, String)>,
+
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Only report root causes in packages whose name starts with NAME, or exactly \
+                NAME@VERSION (repeatable)"
+    )]
+    package: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Hide packages whose name starts with NAME, or exactly NAME@VERSION (repeatable)"
+    )]
+    exclude: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Only show the N root causes that triggered the most rebuilds"
+    )]
+    top: Option<usize>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Hide root causes that rebuilt fewer than N units, counting themselves"
+    )]
+    min_impact: Option<usize>,
+
+    #[arg(
+        long,
+        value_name = "LEVEL",
+        help = "Hide root causes below this severity: info, warn or error"
+    )]
+    min_severity: Option<Severity>,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Write dirty lines that fail to parse, with the error position, to FILE"
+    )]
+    dump_parse_errors: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        value_parser = parse_seconds,
+        help = "Ignore rebuild triggers logged before SECONDS into the cargo run, e.g. 1.5"
+    )]
+    since: Option<Duration>,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Stop cargo and fail when it runs longer than SECONDS"
+    )]
+    timeout: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Save the raw cargo log output to PATH"
+    )]
+    save_log: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Print the root causes separately for each workspace member they rebuilt"
+    )]
+    by_member: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t,
+        conflicts_with = "by_member",
+        help = "Organize the text output by root cause, rebuilt package or reason kind"
+    )]
+    group_by: GroupBy,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "KIND",
+        help = "Exit with status 2 when a rebuild reason of this kind is found"
+    )]
+    fail_on: Option<FailOn>,
+
+    #[arg(
+        long,
+        help = "Exit with status 2 unless every root cause is an edit to a file under the src \
+                directory of the project or of a workspace member, listing the others"
+    )]
+    expect_clean: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "KIND",
+        help = "Print what a kind of rebuild reason means and how to avoid it, without running \
+                cargo"
+    )]
+    explain: Option<ExplainKind>,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Compare the rebuild reasons with a saved analysis in FILE"
+    )]
+    baseline: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Save the analysis to FILE as JSON, for use with --baseline"
+    )]
+    snapshot: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Fail when a dirty line has a rebuild reason that cannot be parsed"
+    )]
+    strict: bool,
+
+    #[arg(
+        long,
+        help = "Print a histogram of how often each kind of reason occurred to stderr"
+    )]
+    histogram: bool,
+
+    #[arg(
+        long,
+        help = "Analyze again whenever a file changes, until interrupted",
+        conflicts_with = "watch_once"
+    )]
+    watch: bool,
+
+    #[arg(
+        long,
+        help = "Run cargo twice and only report the rebuild triggers of the second run that the \
+                first run had too, hiding first build noise",
+        conflicts_with = "input"
+    )]
+    double_run: bool,
+
+    #[arg(
+        long,
+        help = "After the analysis, wait for one file change, print how the rebuild triggers \
+                changed and exit"
+    )]
+    watch_once: bool,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "How long --watch-once waits for a file change",
+        default_value_t = 600
+    )]
+    watch_timeout: u64,
+
+    #[arg(
+        long,
+        help = "Print the cargo command line that would be analyzed and exit without running it"
+    )]
+    dry_run: bool,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Analyze a saved cargo log instead of running cargo (repeatable); rebuilds are \
+                summed across logs"
+    )]
+    input: Vec<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Read the --input files as cargo --message-format=json output and list the units \
+                that were rebuilt",
+        requires = "input"
+    )]
+    message_format_json: bool,
+
     #[arg(help = "Additional arguments to pass to cargo", last = true)]
     cargo_args: Vec<String>,
+
+    #[arg(skip)]
+    metadata: OnceLock<Option<ProjectMetadata>>,
+}
+
+/// What `cargo metadata` tells about the project and its dependencies
+#[derive(Debug)]
+struct ProjectMetadata {
+    /// Number of packages in the dependency graph
+    crates: usize,
+    target_kinds: TargetKinds,
 }
 
 impl Config {
     #[must_use]
     pub fn parse_args() -> Self {
-        if env::args().nth(1).as_deref() == Some("frequent") {
+        let config = if env::args().nth(1).as_deref() == Some("frequent") {
             Self::parse_from(env::args().take(1).chain(env::args().skip(2)))
         } else {
             Self::parse()
+        };
+        // `--json` selects the JSON format, or the JSON rendering of the
+        // frequency report; any other explicit format contradicts it
+        if config.json
+            && !matches!(
+                config.format,
+                OutputFormat::Text | OutputFormat::Json | OutputFormat::Frequency
+            )
+        {
+            Self::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    "--json can only be combined with --format json or --format frequency",
+                )
+                .exit();
         }
+        config
     }
 
     pub fn init_logging(&self) {
@@ -54,88 +577,1209 @@ impl Config {
     }
 
     fn cargo_command(&self) -> String {
-        if self.cargo_args.is_empty() {
-            self.command.clone()
+        self.cargo_words().join(" ")
+    }
+
+    /// The arguments cargo is spawned with: [`Self::cargo_command`] split on
+    /// whitespace, so `--command "test --release"` passes two arguments
+    fn spawn_args(&self) -> Vec<String> {
+        self.cargo_command()
+            .split_whitespace()
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// The cargo subcommand followed by its arguments. `--profile` goes right
+    /// after the subcommand so it stays ahead of a `--` in the cargo arguments.
+    fn cargo_words(&self) -> Vec<&str> {
+        let profile = self
+            .profile
+            .iter()
+            .flat_map(|profile| ["--profile", profile.as_str()]);
+        let target = self
+            .target
+            .iter()
+            .flat_map(|target| ["--target", target.as_str()]);
+        iter::once(self.command.as_str())
+            .chain(profile)
+            .chain(target)
+            .chain(self.cargo_args.iter().map(String::as_str))
+            .collect()
+    }
+
+    /// Reject `--profile` when the cargo arguments already select a profile
+    fn check_profile(&self) -> Result<(), AnalyzerError> {
+        let Some(profile) = &self.profile else {
+            return Ok(());
+        };
+        let conflicting = self
+            .cargo_args
+            .iter()
+            .take_while(|arg| *arg != "--")
+            .find(|arg| *arg == "--release" || arg.split('=').next() == Some("--profile"));
+        conflicting.map_or(Ok(()), |arg| {
+            Err(AnalyzerError::ConflictingProfile {
+                profile: profile.clone(),
+                arg: arg.clone(),
+            })
+        })
+    }
+
+    /// First lines of the text report: the cargo command and, when
+    /// cross-compiling, the target triple
+    fn header(&self) -> String {
+        let command = format!("Running: cargo {}", self.cargo_command());
+        match &self.target {
+            Some(target) => format!("{command}\nTarget: {target}"),
+            None => command,
+        }
+    }
+
+    const fn icon_style(&self) -> IconStyle {
+        if self.ascii {
+            IconStyle::Ascii
+        } else {
+            IconStyle::Emoji
+        }
+    }
+
+    fn cargo_log(&self) -> &str {
+        if self.deep {
+            DEEP_CARGO_LOG
+        } else {
+            &self.cargo_log
+        }
+    }
+
+    /// Environment variables set for the analyzed cargo run
+    fn cargo_env(&self) -> Vec<(&str, &str)> {
+        let rust_log = (!self.no_default_log_override).then_some(("RUST_LOG", "debug"));
+        iter::once(("CARGO_LOG", self.cargo_log()))
+            .chain(rust_log)
+            .chain(
+                self.envs
+                    .iter()
+                    .map(|(key, value)| (key.as_str(), value.as_str())),
+            )
+            .collect()
+    }
+
+    /// Shell command line reproducing the analyzed cargo run
+    fn command_line(&self) -> String {
+        let mut words = vec!["cd".to_string(), shell_quote(&self.path.to_string_lossy())];
+        words.push("&&".to_string());
+        words.extend(
+            self.cargo_env()
+                .into_iter()
+                .map(|(key, value)| format!("{key}={}", shell_quote(value))),
+        );
+        words.push(shell_quote(&self.cargo_bin().to_string_lossy()));
+        words.extend(
+            self.spawn_args()
+                .iter()
+                .map(String::as_str)
+                .map(shell_quote),
+        );
+        words.join(" ")
+    }
+
+    /// Metadata of the analyzed project, fetched once per run. `None` when
+    /// reading `--input` logs, which may come from another project, or when
+    /// `cargo metadata` fails.
+    fn project_metadata(&self) -> Option<&ProjectMetadata> {
+        if !self.input.is_empty() {
+            return None;
+        }
+        self.metadata
+            .get_or_init(|| {
+                project_metadata(&self.cargo_bin(), &self.path)
+                    .inspect_err(|e| debug!("Could not read the project metadata: {e}"))
+                    .ok()
+            })
+            .as_ref()
+    }
+
+    /// The cargo binary to run: `--cargo-bin`, else `$CARGO`, else `cargo`
+    fn cargo_bin(&self) -> PathBuf {
+        self.cargo_bin
+            .clone()
+            .or_else(|| env::var_os("CARGO").map(PathBuf::from))
+            .unwrap_or_else(|| PathBuf::from("cargo"))
+    }
+
+    const fn json_style(&self) -> JsonStyle {
+        if self.compact {
+            JsonStyle::Compact
+        } else {
+            JsonStyle::Pretty
+        }
+    }
+
+    const fn output_format(&self) -> OutputFormat {
+        // The frequency report has its own JSON rendering
+        if self.json && !matches!(self.format, OutputFormat::Frequency) {
+            OutputFormat::Json
         } else {
-            format!("{} {}", self.command, self.cargo_args.join(" "))
+            self.format
         }
     }
 
+    /// Whether root causes in `package` pass the `--package` and `--exclude`
+    /// filters
+    fn is_reported(&self, package: &PackageTarget) -> bool {
+        let included =
+            self.package.is_empty() || self.package.iter().any(|name| package.matches_filter(name));
+        included && !self.is_excluded(package)
+    }
+
+    /// Nodes of `graph` in packages that pass the filters
+    fn reported_nodes<'a>(
+        &self,
+        graph: &'a RebuildGraph,
+    ) -> impl Iterator<Item = &'a RebuildNode> + Clone {
+        graph
+            .nodes()
+            .iter()
+            .filter(|node| self.is_reported(&node.package))
+    }
+
+    fn is_excluded(&self, package: &PackageTarget) -> bool {
+        self.exclude.iter().any(|name| package.matches_filter(name))
+    }
+
     pub fn run(&self) -> Result<(), AnalyzerError> {
-        let cargo_command = self.cargo_command();
+        if let Some(kind) = self.explain {
+            let example = kind.example();
+            let explanation = if self.no_suggestions {
+                example.explanation_without_suggestions(self.icon_style())
+            } else {
+                example.explanation_with(self.icon_style())
+            };
+            println!("{explanation}");
+            return Ok(());
+        }
+
+        if self.message_format_json {
+            return self.list_rebuilt_units();
+        }
+
+        if !self.input.is_empty() {
+            let logs = self
+                .input
+                .iter()
+                .map(|path| {
+                    let lines = BufReader::new(File::open(path)?).lines();
+                    Ok(lines.map(|line| line.map_err(AnalyzerError::from)))
+                })
+                .collect::<Result<Vec<_>, AnalyzerError>>()?;
+            return self
+                .analyze_logs(self.scan_logs(logs, true)?, None)
+                .map(drop);
+        }
 
         let cargo_toml = self.path.join("Cargo.toml");
         if !cargo_toml.exists() {
             return Err(AnalyzerError::CargoTomlNotFound(cargo_toml));
         }
+        self.check_profile()?;
+
+        if self.dry_run {
+            println!("{}", self.command_line());
+            return Ok(());
+        }
+
+        let first = self.analyze_once()?;
+
+        if self.watch {
+            return self.watch();
+        }
+
+        if self.watch_once {
+            let timeout = Duration::from_secs(self.watch_timeout);
+            let mut watcher = FileWatcher::new(&self.path)?;
+            println!("\nWatching {} for changes...", self.path.display());
+
+            if watcher.wait_for_change(timeout)? {
+                let second = self.scan_cargo_run(false)?.graph;
+                let (added, removed) = (second.difference(&first), first.difference(&second));
+                let style = TextStyle {
+                    color: self.color.enabled(),
+                    ..TextStyle::default()
+                };
+                print_trigger_delta(
+                    self.reported_nodes(&added),
+                    self.reported_nodes(&removed),
+                    style,
+                );
+            } else {
+                println!("No changes within {}s.", timeout.as_secs());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Analyze again after every settled batch of file changes. Analysis
+    /// errors are printed and do not stop watching.
+    fn watch(&self) -> Result<(), AnalyzerError> {
+        loop {
+            let mut watcher = FileWatcher::new(&self.path)?;
+            println!("\nWatching {} for changes...", self.path.display());
+
+            while !watcher.wait_for_change(WATCH_DEBOUNCE)? {}
+            watcher.wait_until_settled(WATCH_DEBOUNCE)?;
+
+            if io::stdout().is_terminal() {
+                print!("\x1B[2J\x1B[H");
+            }
+            if let Err(e) = self.analyze_once() {
+                eprintln!("Error: {e}");
+            }
+        }
+    }
+
+    /// Run cargo, report its rebuild triggers and return their graph
+    fn analyze_once(&self) -> Result<RebuildGraph, AnalyzerError> {
+        let cargo_command = self.cargo_command();
 
         info!(
             "Analyzing output of `cargo {}` on project {}",
             cargo_command,
             self.path.display()
         );
+        if self.output_format() == OutputFormat::Text && !self.quiet {
+            println!("{}", self.header());
+        }
+
+        let first_run = if self.double_run {
+            Some(self.first_run()?)
+        } else {
+            None
+        };
 
-        let args: Vec<&str> = cargo_command.split_whitespace().collect();
+        let scan = self.scan_cargo_run(true)?;
+        self.analyze_logs(scan, first_run.as_ref())
+    }
+
+    fn spawn_cargo(&self) -> Result<Child, AnalyzerError> {
+        let args = self.spawn_args();
         let (cmd, cmd_args) = args.split_first().ok_or(AnalyzerError::EmptyCommand)?;
 
-        let output = Command::new("cargo")
+        let cargo_bin = self.cargo_bin();
+        Command::new(&cargo_bin)
             .arg(cmd)
             .args(cmd_args)
             .current_dir(&self.path)
-            .env("CARGO_LOG", "cargo::core::compiler::fingerprint=info")
-            .env("RUST_LOG", "debug")
+            .envs(self.cargo_env())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
-            .spawn()?;
+            .spawn()
+            .map_err(|e| spawn_error(&cargo_bin, e))
+    }
+
+    /// Run cargo without reporting and collect its rebuild triggers, which
+    /// `--double-run` compares the second run against
+    fn first_run(&self) -> Result<RebuildGraph, AnalyzerError> {
+        info!("First of two cargo runs");
+        self.scan_cargo_run(false).map(|scan| scan.graph)
+    }
+
+    /// Run cargo and scan its log, stopping cargo when `--timeout` passes
+    fn scan_cargo_run(&self, emit: bool) -> Result<LogScan, AnalyzerError> {
+        let mut child = self.spawn_cargo()?;
+        let timeout = self.timeout.map(Duration::from_secs);
+        let scan = child.stderr.take().map_or_else(
+            || Ok(LogScan::default()),
+            |stderr| self.scan_logs([read_lines(stderr, timeout)], emit),
+        );
+        if matches!(scan, Err(AnalyzerError::Timeout(_))) {
+            child.kill()?;
+        }
+        child.wait()?;
+
+        scan
+    }
 
-        if let Some(stderr) = output.stderr {
-            let reader = BufReader::new(stderr);
-            self.analyze_logs(reader)?;
+    /// List the units that cargo's JSON messages in the `--input` files report
+    /// as rebuilt
+    fn list_rebuilt_units(&self) -> Result<(), AnalyzerError> {
+        let mut units = Vec::new();
+        for path in &self.input {
+            for line in BufReader::new(File::open(path)?).lines() {
+                units.extend(parse_rebuilt_unit(&line?));
+            }
         }
 
+        if self.json {
+            println!("{}", self.json_style().serialize(&units)?);
+        } else if units.is_empty() {
+            println!("No units were rebuilt.");
+        } else {
+            println!(
+                "{} unit{} rebuilt:",
+                units.len(),
+                if units.len() == 1 { "" } else { "s" }
+            );
+            for unit in &units {
+                println!("  {unit}");
+            }
+        }
         Ok(())
     }
 
-    fn analyze_logs(&self, reader: BufReader<ChildStderr>) -> Result<(), AnalyzerError> {
-        let mut graph = RebuildGraph::new();
+    /// Collect the rebuild triggers of `logs` into a graph, one log per cargo
+    /// run. With `emit`, the lines also go to `--save-log`, parse failures to
+    /// `--dump-parse-errors` and each trigger to the ndjson output; a first
+    /// of two runs is only scanned.
+    fn scan_logs(
+        &self,
+        logs: impl IntoIterator<Item = impl Iterator<Item = Result<String, AnalyzerError>>>,
+        emit: bool,
+    ) -> Result<LogScan, AnalyzerError> {
+        let mut scan = LogScan::default();
+        let mut hints = UnitHints::default();
+        let create = |path: &Option<PathBuf>| {
+            path.as_ref()
+                .filter(|_| emit)
+                .map(File::create)
+                .transpose()
+                .map(|file| file.map(BufWriter::new))
+        };
+        let mut parse_errors = create(&self.dump_parse_errors)?;
+        let mut saved_log = create(&self.save_log)?;
 
-        for line in reader.lines() {
-            let line = line?;
-            debug!("Cargo log: {line}");
+        for log in logs {
+            let mut log_graph = RebuildGraph::new();
+            for line in log {
+                let line = line?;
+                debug!("Cargo log: {line}");
 
-            if line.contains("fingerprint") && (line.contains("dirty:") || line.contains("stale:"))
-            {
-                debug!("Rebuild trigger detected: {line}");
-                if let Some(entry) = parse_rebuild_entry(&line) {
-                    graph.add_node(RebuildNode::new(entry.package, entry.reason));
+                if let Some(writer) = &mut saved_log {
+                    writeln!(writer, "{line}")?;
+                }
+
+                scan.fingerprint_seen |= line.contains("fingerprint");
+                hints.observe(&line);
+                // A unit without a previous fingerprint is built for the first time
+                if line.contains("fingerprint error for") {
+                    scan.first_builds
+                        .insert(parse_package_target(&line).package_id);
+                }
+                if self.is_trigger_line(&line) {
+                    debug!("Rebuild trigger detected: {line}");
+                    if line.contains("dirty:")
+                        && let Err(failure) = parse_rebuild_reason_verbose(&line)
+                    {
+                        debug!("Could not parse rebuild reason: {failure}");
+                        if let Some(writer) = &mut parse_errors {
+                            write_parse_failure(writer, &line, &failure)?;
+                        }
+                        if self.strict {
+                            scan.unparsed.push(line.clone());
+                        }
+                    }
+                    for entry in parse_rebuild_entry(&line) {
+                        self.record_entry(
+                            hints.apply(entry),
+                            emit,
+                            &mut scan.histogram,
+                            &mut log_graph,
+                        )?;
+                    }
                 }
-            }
 
-            if line.contains("recompiling") || line.contains("compiling") {
-                debug!("Compilation: {line}");
+                if line.contains("recompiling") || line.contains("compiling") {
+                    debug!("Compilation: {line}");
+                }
+                scan.compiled.extend(parse_compiled_package(&line));
             }
+            scan.graph.merge(log_graph);
         }
 
-        if self.json {
-            println!("{}", graph.to_json()?);
+        for mut writer in [parse_errors, saved_log].into_iter().flatten() {
+            writer.flush()?;
+        }
+        Ok(scan)
+    }
+
+    /// Report the triggers found by `scan`, keeping only those that
+    /// `first_run` had too when given, and return the reported graph
+    fn analyze_logs(
+        &self,
+        scan: LogScan,
+        first_run: Option<&RebuildGraph>,
+    ) -> Result<RebuildGraph, AnalyzerError> {
+        let LogScan {
+            mut graph,
+            histogram,
+            fingerprint_seen,
+            compiled,
+            first_builds,
+            unparsed,
+        } = scan;
+        if let Some(first_run) = first_run {
+            graph = graph.intersection(first_run);
+        }
+        graph.annotate_root_causes();
+
+        if graph.is_empty() {
+            info!("No rebuild triggers in the cargo log");
         } else {
-            let root_causes = graph.root_causes();
+            info!(
+                "{} rebuild triggers, {} unique rebuilt units",
+                graph.len() + graph.duplicate_count(),
+                graph.len()
+            );
+        }
+
+        if self.histogram {
+            eprint!("{}", histogram.render());
+        }
+
+        // A no-op build logs nothing at info level, so only complain when cargo
+        // compiled something without telling us why
+        let fingerprints_missing = !compiled.is_empty() && !fingerprint_seen;
+        if fingerprints_missing {
+            self.warn_missing_fingerprints();
+        } else {
+            if is_first_build(&compiled, &first_builds, &graph) {
+                eprintln!(
+                    "note: This looks like a first/clean build; rebuild analysis is only \
+                     meaningful on incremental builds."
+                );
+            }
+            for notice in compilation_mismatches(&compiled, &first_builds, &graph) {
+                eprintln!("note: {notice}");
+            }
+        }
+
+        self.report(&graph, fingerprints_missing)?;
+
+        for line in &unparsed {
+            eprintln!("warning: could not parse rebuild reason: {line}");
+        }
+        unparsed.into_iter().next().map_or(Ok(graph), |line| {
+            Err(AnalyzerError::UnparsedReason { line })
+        })
+    }
+
+    /// Lines handed to the parser: cargo's fingerprint `dirty:` and `stale:`
+    /// lines, plus any line containing a `--match` substring
+    fn is_trigger_line(&self, line: &str) -> bool {
+        (line.contains("fingerprint") && (line.contains("dirty:") || line.contains("stale:")))
+            || self
+                .trigger_markers
+                .iter()
+                .any(|marker| line.contains(marker))
+    }
+
+    fn record_entry(
+        &self,
+        entry: ParsedRebuildEntry,
+        emit: bool,
+        histogram: &mut ReasonHistogram,
+        graph: &mut RebuildGraph,
+    ) -> Result<(), AnalyzerError> {
+        if let Some(timestamp) = entry.timestamp {
+            debug!("Trigger for {} at {timestamp:?}", entry.package);
+            if self.since.is_some_and(|since| timestamp < since) {
+                return Ok(());
+            }
+        }
+        if emit && self.output_format() == OutputFormat::Ndjson {
+            write_ndjson_event(&entry, &mut io::stdout().lock())?;
+        }
+        histogram.record(&entry.reason);
+        graph.add_node(
+            RebuildNode::new(entry.package, entry.reason).with_timestamp(entry.timestamp),
+        );
+        Ok(())
+    }
+
+    fn warn_missing_fingerprints(&self) {
+        eprintln!(
+            "warning: cargo printed no fingerprint output. Cargo may be too old to log \
+             fingerprints, or the CARGO_LOG spec `{}` does not enable \
+             cargo::core::compiler::fingerprint=info.",
+            self.cargo_log()
+        );
+    }
 
-            if root_causes.is_empty() {
-                println!("No rebuild triggers detected.");
+    /// Print the analysis in the selected format, then handle snapshots,
+    /// baselines and `--fail-on`
+    fn report(
+        &self,
+        graph: &RebuildGraph,
+        fingerprints_missing: bool,
+    ) -> Result<(), AnalyzerError> {
+        match self.output_format() {
+            OutputFormat::Text if self.quiet => {
+                print!("{}", RebuildSummary::from_nodes(self.reported_nodes(graph)));
+            }
+            #[cfg(feature = "tui")]
+            OutputFormat::Text if self.tui => tui::run(self.shown_chains(graph).0)?,
+            OutputFormat::Text => self.print_text_report(graph, fingerprints_missing)?,
+            OutputFormat::Json => {
+                let mut chains = self.reported_chains(graph);
+                self.limit_to_top(&mut chains);
+                if self.json_flat {
+                    println!("{}", to_json(&chains, self.json_style())?);
+                } else {
+                    println!(
+                        "{}",
+                        RebuildAnalysis::to_versioned_json(&chains, graph, self.json_style())?
+                    );
+                }
+            }
+            OutputFormat::JsonTree => {
+                let mut chains = self.reported_chains(graph);
+                self.limit_to_top(&mut chains);
+                let trees: Vec<_> = chains
+                    .iter()
+                    .filter_map(|chain| cascade_tree(graph, chain))
+                    .map(|mut tree| {
+                        tree.retain(&|node| !self.is_excluded(&node.package));
+                        tree
+                    })
+                    .collect();
+                println!("{}", trees_to_json(&trees, self.json_style())?);
+            }
+            OutputFormat::Mermaid => print!("{}", to_mermaid(&self.reported_chains(graph))),
+            OutputFormat::Csv => write_csv(self.reported_nodes(graph), &mut io::stdout().lock())?,
+            OutputFormat::Ndjson => {}
+            OutputFormat::NodesJson => {
+                let nodes: Vec<_> = self.reported_nodes(graph).collect();
+                println!("{}", nodes_to_json(&nodes, self.json_style())?);
+            }
+            OutputFormat::Frequency => self.print_frequencies(graph)?,
+            OutputFormat::Markdown => print!("{}", to_markdown(&self.reported_chains(graph))),
+            OutputFormat::Kv => print!("{}", to_key_values(&self.reported_chains(graph))),
+            OutputFormat::Junit => print!(
+                "{}",
+                to_junit(&self.reported_chains(graph), |kind| {
+                    self.fail_on.is_some_and(|fail_on| fail_on.matches(kind))
+                })
+            ),
+        }
+
+        if let Some(path) = &self.snapshot {
+            let writer = BufWriter::new(File::create(path)?);
+            serde_json::to_writer_pretty(writer, &RebuildAnalysis::from_graph(graph))?;
+        }
+
+        if let Some(path) = &self.baseline {
+            let baseline: RebuildAnalysis =
+                serde_json::from_reader(BufReader::new(File::open(path)?))?;
+            let diff = RebuildAnalysis::from_graph(graph).diff(&baseline);
+            if self.output_format() == OutputFormat::Text {
+                print!("\n{diff}");
             } else {
-                println!(
-                    "\n{} root cause{}:",
-                    root_causes.len(),
-                    if root_causes.len() == 1 { "" } else { "s" }
+                eprint!("{diff}");
+            }
+        }
+
+        if let Some(fail_on) = self.fail_on
+            && let Some(node) = graph
+                .nodes()
+                .iter()
+                .find(|node| fail_on.matches(node.reason.kind()) && self.is_reported(&node.package))
+        {
+            return Err(AnalyzerError::RebuildTriggered(format!(
+                "{} in {}",
+                node.reason, node.package
+            )));
+        }
+
+        if self.expect_clean {
+            let unexpected = self.unexpected_triggers(graph);
+            for node in &unexpected {
+                eprintln!(
+                    "unexpected rebuild trigger: {} in {}",
+                    node.reason, node.package
                 );
+            }
+            if let Some(node) = unexpected.first() {
+                return Err(AnalyzerError::RebuildTriggered(format!(
+                    "{} in {}",
+                    node.reason, node.package
+                )));
+            }
+        }
 
-                for root in &root_causes {
-                    println!("  {} {}", root.package, root.reason);
+        Ok(())
+    }
+
+    /// Reported root causes other than edits to the project's own sources,
+    /// which `--expect-clean` rejects
+    fn unexpected_triggers<'a>(&self, graph: &'a RebuildGraph) -> Vec<&'a RebuildNode> {
+        let root = self.canonical_root();
+        let source_dirs = self.source_dirs(&root);
+        graph
+            .nodes()
+            .iter()
+            .filter(|node| node.is_root_cause() && self.is_reported(&node.package))
+            .filter(|node| !is_project_source_edit(&node.reason, &root, &source_dirs))
+            .collect()
+    }
+
+    /// The `src` directories of `root` and of every workspace member. Only
+    /// `root/src` without a manifest, e.g. when checking a copied log.
+    fn source_dirs(&self, root: &Path) -> Vec<PathBuf> {
+        let members = if root.join("Cargo.toml").is_file() {
+            workspace_members(&self.cargo_bin(), root)
+                .inspect_err(|e| debug!("Could not list the workspace members: {e}"))
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        iter::once(root.to_path_buf())
+            .chain(members.into_iter().map(|member| member.dir))
+            .map(|dir| dir.join("src"))
+            .collect()
+    }
+
+    // Cargo logs absolute paths, so they are compared against the canonical
+    // project root
+    fn canonical_root(&self) -> PathBuf {
+        self.path
+            .canonicalize()
+            .unwrap_or_else(|_| self.path.clone())
+    }
+
+    /// Print the reasons and crates ranked by rebuilt units of the reported
+    /// packages, as JSON with `--json`
+    fn print_frequencies(&self, graph: &RebuildGraph) -> Result<(), AnalyzerError> {
+        let keep = |node: &RebuildNode| self.is_reported(&node.package);
+        if self.json {
+            println!(
+                "{}",
+                self.json_style()
+                    .serialize(&FrequencyReport::from_graph(graph, keep))?
+            );
+        } else {
+            print!(
+                "{}",
+                to_frequency_list(
+                    &graph.reason_frequencies(keep),
+                    &graph.package_frequencies(keep)
+                )
+            );
+        }
+        Ok(())
+    }
+
+    /// Print root causes with their explanations and hints
+    fn print_text_report(
+        &self,
+        graph: &RebuildGraph,
+        fingerprints_missing: bool,
+    ) -> Result<(), AnalyzerError> {
+        let (chains, omitted) = self.shown_chains(graph);
+        let roots: Vec<_> = chains.iter().collect();
+        let project_root = (!self.abs_paths).then(|| self.canonical_root());
+        let style = TextStyle {
+            color: self.color.enabled(),
+            icons: self.icon_style(),
+            expand: self.expand,
+            project_root: project_root.as_deref(),
+            total_crates: self.total_crates(),
+            hide_suggestions: self.no_suggestions,
+            target_kinds: self
+                .project_metadata()
+                .map(|metadata| &metadata.target_kinds),
+        };
+        let reported = self.reported_nodes(graph);
+        // The warning replaces the clean build message
+        if !fingerprints_missing {
+            if self.by_member {
+                self.print_by_member(&chains, style)?;
+            } else {
+                match self.group_by {
+                    GroupBy::Root => print_root_causes(&roots, omitted, style),
+                    GroupBy::Package => print_by_package(reported.clone(), style),
+                    GroupBy::Reason => print_by_reason_kind(reported.clone(), style),
                 }
             }
         }
+        if !fingerprints_missing && self.group_by != GroupBy::Package {
+            print_targets_by_package(&targets_by_package(reported), style);
+        }
+        if let Some(hint) = relocation_hint(graph.nodes(), Some(&self.canonical_root())) {
+            println!("\n{hint}");
+        }
+        if let Some((first, last)) = graph.trigger_timespan() {
+            println!(
+                "\nTriggers spanned {:.2}s-{:.2}s",
+                first.as_secs_f64(),
+                last.as_secs_f64()
+            );
+        }
+        if !fingerprints_missing {
+            let mut by_impact = graph.root_causes_by_impact();
+            by_impact.retain(|chain| self.is_reported_root(&chain.root_cause));
+            println!("\n{}", bottom_line(&by_impact));
+        }
+        Ok(())
+    }
+
+    /// Number of packages in the analyzed project's dependency graph, known
+    /// only when cargo was run on it
+    fn total_crates(&self) -> Option<usize> {
+        self.project_metadata().map(|metadata| metadata.crates)
+    }
 
+    fn print_by_member(
+        &self,
+        chains: &[RootCauseChain],
+        style: TextStyle<'_>,
+    ) -> Result<(), AnalyzerError> {
+        for WorkspaceMember { name, .. } in workspace_members(&self.cargo_bin(), &self.path)? {
+            let roots: Vec<_> = chains
+                .iter()
+                .filter(|chain| chain.involves(&name))
+                .collect();
+            if !roots.is_empty() {
+                println!("\n== {name} ==");
+                print_root_causes(&roots, 0, style);
+            }
+        }
         Ok(())
     }
+
+    /// Root cause chains with the package filters applied, largest impact
+    /// first when `--top` is given
+    fn reported_chains(&self, graph: &RebuildGraph) -> Vec<RootCauseChain> {
+        let mut chains = if self.top.is_some() {
+            graph.root_causes_by_impact()
+        } else {
+            graph.root_cause_chains()
+        };
+        chains.retain(|chain| self.is_reported_root(&chain.root_cause));
+        for chain in &mut chains {
+            chain
+                .affected_packages
+                .retain(|node| !self.is_excluded(&node.package));
+        }
+        chains
+    }
+
+    /// Whether a root cause passes the package filters and `--min-severity`
+    fn is_reported_root(&self, root: &RebuildNode) -> bool {
+        self.is_reported(&root.package)
+            && self
+                .min_severity
+                .is_none_or(|min| root.reason.severity() >= min)
+    }
+
+    /// Reported chains left after `--min-impact` and `--top`, most severe
+    /// first for reading, with the number of chains these left out
+    fn shown_chains(&self, graph: &RebuildGraph) -> (Vec<RootCauseChain>, usize) {
+        let mut chains = self.reported_chains(graph);
+        let omitted = self.drop_low_impact(&mut chains) + self.limit_to_top(&mut chains);
+        chains.sort_by_key(|chain| Reverse(chain.root_cause.reason.severity()));
+        (chains, omitted)
+    }
+
+    /// With `--min-impact`, drop chains that rebuilt too few units and return
+    /// how many were dropped
+    fn drop_low_impact(&self, chains: &mut Vec<RootCauseChain>) -> usize {
+        let Some(min_impact) = self.min_impact else {
+            return 0;
+        };
+        let before = chains.len();
+        chains.retain(|chain| chain.total_rebuilds() >= min_impact);
+        before - chains.len()
+    }
+
+    /// With `--top`, keep only the first chains and return how many were
+    /// dropped
+    fn limit_to_top(&self, chains: &mut Vec<RootCauseChain>) -> usize {
+        let Some(top) = self.top else {
+            return 0;
+        };
+        let omitted = chains.len().saturating_sub(top);
+        chains.truncate(top);
+        omitted
+    }
+}
+
+/// Lines of cargo's stderr, read on a separate thread so that reading can give
+/// up with [`AnalyzerError::Timeout`] once `timeout` has passed
+fn read_lines(
+    stderr: ChildStderr,
+    timeout: Option<Duration>,
+) -> impl Iterator<Item = Result<String, AnalyzerError>> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for line in BufReader::new(stderr).lines() {
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    let deadline = timeout.map(|timeout| (Instant::now() + timeout, timeout));
+    iter::from_fn(move || {
+        let line = match deadline {
+            Some((deadline, timeout)) => {
+                match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                    Ok(line) => line,
+                    Err(RecvTimeoutError::Timeout) => {
+                        return Some(Err(AnalyzerError::Timeout(timeout)));
+                    }
+                    Err(RecvTimeoutError::Disconnected) => return None,
+                }
+            }
+            None => receiver.recv().ok()?,
+        };
+        Some(line.map_err(AnalyzerError::from))
+    })
+}
+
+/// Quote `word` for a POSIX shell unless it only has safe characters
+fn shell_quote(word: &str) -> String {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "-_./:=,+@".contains(c);
+    if !word.is_empty() && word.chars().all(is_safe) {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', r"'\''"))
+    }
+}
+
+/// Split a `KEY=VALUE` argument of `--env`
+fn parse_env_assignment(assignment: &str) -> Result<(String, String), String> {
+    match assignment.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected KEY=VALUE, got `{assignment}`")),
+    }
+}
+
+/// Parse a non-negative, possibly fractional number of seconds
+fn parse_seconds(seconds: &str) -> Result<Duration, String> {
+    seconds
+        .parse()
+        .ok()
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+        .ok_or_else(|| format!("expected a number of seconds, got `{seconds}`"))
+}
+
+/// Tell a missing cargo binary apart from other failures to spawn it
+fn spawn_error(cargo_bin: &Path, error: io::Error) -> AnalyzerError {
+    if error.kind() == io::ErrorKind::NotFound {
+        AnalyzerError::CargoNotFound(cargo_bin.to_path_buf())
+    } else {
+        error.into()
+    }
+}
+
+/// A package of the workspace, as listed by `cargo metadata`
+struct WorkspaceMember {
+    name: String,
+    /// Directory holding the member's `Cargo.toml`
+    dir: PathBuf,
+}
+
+/// The workspace members under `path`
+fn workspace_members(cargo_bin: &Path, path: &Path) -> Result<Vec<WorkspaceMember>, AnalyzerError> {
+    let output = Command::new(cargo_bin)
+        .args(["metadata", "--no-deps", "--format-version", "1"])
+        .current_dir(path)
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|e| spawn_error(cargo_bin, e))?;
+    if !output.status.success() {
+        return Err(
+            io::Error::other(format!("cargo metadata failed with {}", output.status)).into(),
+        );
+    }
+
+    let metadata: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    Ok(metadata["packages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|package| {
+            let manifest = Path::new(package["manifest_path"].as_str()?);
+            Some(WorkspaceMember {
+                name: package["name"].as_str()?.to_string(),
+                dir: manifest.parent()?.to_path_buf(),
+            })
+        })
+        .collect())
+}
+
+/// Crate count and target kinds of the project and all its dependencies.
+/// Runs offline and with the current lockfile so that it never resolves or
+/// downloads.
+fn project_metadata(cargo_bin: &Path, path: &Path) -> Result<ProjectMetadata, AnalyzerError> {
+    let output = Command::new(cargo_bin)
+        .args(["metadata", "--format-version", "1", "--offline", "--locked"])
+        .current_dir(path)
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|e| spawn_error(cargo_bin, e))?;
+    if !output.status.success() {
+        return Err(
+            io::Error::other(format!("cargo metadata failed with {}", output.status)).into(),
+        );
+    }
+
+    let metadata: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    let packages = metadata["packages"]
+        .as_array()
+        .map_or(&[][..], Vec::as_slice);
+    let mut target_kinds = TargetKinds::default();
+    for package in packages {
+        let Some(name) = package["name"].as_str() else {
+            continue;
+        };
+        for target in package["targets"].as_array().into_iter().flatten() {
+            let kinds = target["kind"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(serde_json::Value::as_str);
+            if let Some(target_name) = target["name"].as_str() {
+                target_kinds.insert(name, target_name, TargetKind::from_cargo_kinds(kinds));
+            }
+        }
+    }
+    Ok(ProjectMetadata {
+        crates: packages.len(),
+        target_kinds,
+    })
+}
+
+/// Cross-check the packages cargo compiled against those with a captured
+/// rebuild reason or built for the first time. Without any progress lines in
+/// the log there is nothing to compare, so dirty packages are only flagged
+/// when others were compiled.
+fn compilation_mismatches(
+    compiled: &BTreeSet<String>,
+    first_builds: &BTreeSet<String>,
+    graph: &RebuildGraph,
+) -> Vec<String> {
+    let mut explained = first_builds.clone();
+    explained.extend(
+        graph
+            .nodes()
+            .iter()
+            .map(|node| node.package.package_id.clone()),
+    );
+    let unexplained = compiled.difference(&explained).map(|package| {
+        format!("{package} recompiled without explanation; its rebuild reason was not captured")
+    });
+    let uncompiled = explained
+        .difference(compiled)
+        .filter(|package| !compiled.is_empty() && *package != "unknown")
+        .map(|package| format!("{package} has a rebuild reason but cargo did not recompile it"));
+    unexplained.chain(uncompiled).collect()
+}
+
+/// Whether most compiled units had no previous fingerprint, or the graph
+/// itself looks like a build from scratch
+fn is_first_build(
+    compiled: &BTreeSet<String>,
+    first_builds: &BTreeSet<String>,
+    graph: &RebuildGraph,
+) -> bool {
+    let fresh = compiled.intersection(first_builds).count();
+    (fresh > 0 && fresh * 2 >= compiled.len()) || graph.looks_like_first_build()
+}
+
+/// Whether `reason` is a change to a file in one of `source_dirs`, such as
+/// `src/lib.rs` or `lib-a/src/lib.rs`, rather than in generated or vendored
+/// code. Relative paths are taken relative to the project `root`.
+fn is_project_source_edit(reason: &RebuildReason, root: &Path, source_dirs: &[PathBuf]) -> bool {
+    let RebuildReason::FileChanged { path, .. } = reason else {
+        return false;
+    };
+    let path = root.join(path);
+    source_dirs.iter().any(|dir| path.starts_with(dir))
+}
+
+/// Write a failed line with a caret marking where parsing stopped
+fn write_parse_failure(
+    writer: &mut impl Write,
+    line: &str,
+    failure: &ParseFailure,
+) -> Result<(), AnalyzerError> {
+    let column = line[..failure.offset].chars().count();
+    writeln!(writer, "{line}")?;
+    writeln!(writer, "{:column$}^ {failure}", "")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rebuild_graph::tests::cc_cascade_graph;
+
+    #[test]
+    fn strict_mode_reports_unparsed_reasons() {
+        let config = Config::parse_from(["cargo-frequent", "--strict", "--format", "csv"]);
+        let log = [
+            r#"    0.010000000s  INFO prepare_target{force=false package_id=app v0.1.0 target="app"}: cargo::core::compiler::fingerprint:     dirty: ProfileConfigurationChanged"#,
+            r#"    0.020000000s  INFO prepare_target{force=false package_id=app v0.1.0 target="app"}: cargo::core::compiler::fingerprint:     dirty: SomethingNew {}"#,
+        ];
+
+        let scan = config
+            .scan_logs([log.into_iter().map(|line| Ok(line.to_string()))], true)
+            .unwrap();
+        let result = config.analyze_logs(scan, None);
+
+        assert!(
+            matches!(
+                &result,
+                Err(AnalyzerError::UnparsedReason { line }) if line.contains("SomethingNew {}")
+            ),
+            "Expected the unparsed line in the error, got: {result:?}"
+        );
+    }
+
+    #[test]
+    fn first_run_scan_applies_the_same_filters() {
+        let config = Config::parse_from(["cargo-frequent", "--since", "1"]);
+        let log = [
+            r#"    0.500000000s  INFO prepare_target{force=false package_id=app v0.1.0 target="app"}: cargo::core::compiler::fingerprint:     dirty: ProfileConfigurationChanged"#,
+            r#"    2.000000000s  INFO prepare_target{force=false package_id=lib-a v0.1.0 target="lib_a"}: cargo::core::compiler::fingerprint:     dirty: ProfileConfigurationChanged"#,
+        ];
+        let scan = |emit| {
+            config
+                .scan_logs([log.into_iter().map(|line| Ok(line.to_string()))], emit)
+                .unwrap()
+                .graph
+        };
+
+        for graph in [scan(false), scan(true)] {
+            let packages: Vec<_> = graph
+                .nodes()
+                .iter()
+                .map(|node| node.package.name())
+                .collect();
+            assert_eq!(packages, ["lib-a"]);
+        }
+    }
+
+    #[test]
+    fn min_impact_hides_small_chains() {
+        let config = Config::parse_from(["cargo-frequent", "--min-impact", "2"]);
+        let mut graph = cc_cascade_graph();
+        graph.add_node(RebuildNode::new(
+            PackageTarget::new("app v0.1.0", None),
+            RebuildReason::ProfileConfigurationChanged,
+        ));
+        let mut chains = config.reported_chains(&graph);
+
+        let omitted = config.drop_low_impact(&mut chains);
+
+        assert_eq!(omitted, 1);
+        assert_eq!(chains.len(), 1);
+        assert!(
+            chains[0].root_cause.package.is_package("libz-sys"),
+            "The larger chain should be kept: {chains:?}"
+        );
+    }
+
+    #[test]
+    fn only_the_text_report_orders_by_severity() {
+        let mut graph = RebuildGraph::new();
+        graph.add_node(RebuildNode::new(
+            PackageTarget::new("app v0.1.0", None),
+            RebuildReason::FileChanged {
+                path: "src/main.rs".to_string(),
+                reference: None,
+            },
+        ));
+        graph.add_node(RebuildNode::new(
+            PackageTarget::new("tool v0.1.0", None),
+            RebuildReason::Unknown("SomethingNew".to_string()),
+        ));
+        let first_root =
+            |chains: &[RootCauseChain]| chains[0].root_cause.package.package_id.clone();
+
+        for args in [&["cargo-frequent"][..], &["cargo-frequent", "--top", "2"]] {
+            let config = Config::parse_from(args);
+            assert_eq!(
+                first_root(&config.shown_chains(&graph).0),
+                "tool v0.1.0",
+                "The unknown reason should be shown first with {args:?}"
+            );
+        }
+        let config = Config::parse_from(["cargo-frequent"]);
+        assert_eq!(
+            first_root(&config.reported_chains(&graph)),
+            "app v0.1.0",
+            "Machine formats keep the canonical order"
+        );
+    }
+
+    #[test]
+    fn profile_is_passed_to_cargo() {
+        let config = Config::parse_from(["cargo-frequent", "--profile", "release", "--", "--lib"]);
+        assert_eq!(config.cargo_command(), "check --profile release --lib");
+        assert!(config.check_profile().is_ok());
+
+        let conflicting =
+            Config::parse_from(["cargo-frequent", "--profile", "bench", "--", "--release"]);
+        assert!(matches!(
+            conflicting.check_profile(),
+            Err(AnalyzerError::ConflictingProfile { arg, .. }) if arg == "--release"
+        ));
+    }
+
+    #[test]
+    fn target_is_passed_to_cargo_and_shown_in_header() {
+        let config = Config::parse_from([
+            "cargo-frequent",
+            "--profile",
+            "release",
+            "--target",
+            "aarch64-unknown-linux-gnu",
+            "--",
+            "--lib",
+        ]);
+        assert_eq!(
+            config.cargo_command(),
+            "check --profile release --target aarch64-unknown-linux-gnu --lib"
+        );
+        assert_eq!(
+            config.header(),
+            "Running: cargo check --profile release --target aarch64-unknown-linux-gnu \
+             --lib\nTarget: aarch64-unknown-linux-gnu"
+        );
+    }
+
+    #[test]
+    fn deep_logs_fingerprints_at_debug_level() {
+        let config = Config::parse_from(["cargo-frequent", "--deep"]);
+        assert!(config.cargo_env().contains(&("CARGO_LOG", DEEP_CARGO_LOG)));
+    }
+
+    #[test]
+    fn only_member_src_directories_hold_project_sources() {
+        let root = Path::new("/ws");
+        let source_dirs = [root.join("src"), root.join("lib-a/src")];
+        let edit = |path: &str| RebuildReason::FileChanged {
+            path: path.to_string(),
+            reference: None,
+        };
+
+        for path in ["src/main.rs", "/ws/lib-a/src/lib.rs"] {
+            assert!(
+                is_project_source_edit(&edit(path), root, &source_dirs),
+                "{path} is a project source"
+            );
+        }
+        for path in [
+            "/ws/target/debug/build/app-1234/out/src/generated.rs",
+            "vendor/serde/src/lib.rs",
+            "/other/src/lib.rs",
+        ] {
+            assert!(
+                !is_project_source_edit(&edit(path), root, &source_dirs),
+                "{path} is not a project source"
+            );
+        }
+    }
 }
@@ -0,0 +1,104 @@
+//! Polling file watcher for re-running the analysis after edits
+//!
+//! Snapshots the modification times of all files below a project root
+//! (skipping `target/` and hidden directories) every 200 ms and compares
+//! snapshots until something changes. It polls rather than subscribing to OS
+//! file notifications, so each poll walks the whole tree. Files that vanish
+//! mid-walk, like editor swap files, are left out of the snapshot.
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+type Snapshot = HashMap<PathBuf, SystemTime>;
+
+/// Watches all files below a project root for changes
+pub struct FileWatcher {
+    root: PathBuf,
+    snapshot: Snapshot,
+}
+
+impl FileWatcher {
+    /// Start watching `root`; changes are detected relative to this moment
+    pub fn new(root: &Path) -> io::Result<Self> {
+        Ok(Self {
+            root: root.to_path_buf(),
+            snapshot: snapshot(root)?,
+        })
+    }
+
+    /// Block until a file is created, modified or removed.
+    ///
+    /// Returns `false` if nothing changed before `timeout` elapsed.
+    pub fn wait_for_change(&mut self, timeout: Duration) -> io::Result<bool> {
+        let deadline = Instant::now() + timeout;
+
+        while Instant::now() < deadline {
+            thread::sleep(POLL_INTERVAL);
+            let current = snapshot(&self.root)?;
+            if current != self.snapshot {
+                self.snapshot = current;
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Block until no file has changed for `quiet`, so a burst of saves is
+    /// handled as one change
+    pub fn wait_until_settled(&mut self, quiet: Duration) -> io::Result<()> {
+        while self.wait_for_change(quiet)? {}
+        Ok(())
+    }
+}
+
+fn snapshot(root: &Path) -> io::Result<Snapshot> {
+    let mut files = Snapshot::new();
+    collect_mtimes(root, &mut files)?;
+    Ok(files)
+}
+
+fn collect_mtimes(dir: &Path, files: &mut Snapshot) -> io::Result<()> {
+    let Some(entries) = skip_vanished(fs::read_dir(dir))? else {
+        return Ok(());
+    };
+    for entry in entries {
+        skip_vanished(collect_entry(&entry?, files))?;
+    }
+    Ok(())
+}
+
+fn collect_entry(entry: &fs::DirEntry, files: &mut Snapshot) -> io::Result<()> {
+    let path = entry.path();
+    if entry.file_type()?.is_dir() {
+        if !is_ignored_dir(&path) {
+            collect_mtimes(&path, files)?;
+        }
+    } else {
+        files.insert(path, entry.metadata()?.modified()?);
+    }
+    Ok(())
+}
+
+/// `None` for a file that disappeared after it was listed, such as the swap
+/// and backup files editors create and remove while saving
+fn skip_vanished<T>(result: io::Result<T>) -> io::Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn is_ignored_dir(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name == "target" || name.starts_with('.'))
+}
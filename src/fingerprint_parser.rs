@@ -3,86 +3,291 @@
 //! This module uses the nom parser combinator library to parse cargo's
 //! fingerprint log output and extract structured rebuild reasons.
 
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    iter::repeat,
+    time::Duration,
+};
+
 use nom::{
     IResult,
     branch::alt,
-    bytes::complete::{tag, take_until},
-    character::complete::{char, digit1, space0},
-    combinator::map,
-    error::Error,
-    sequence::{delimited, preceded, terminated, tuple},
+    bytes::complete::{escaped_transform, is_not, tag, take_until},
+    character::complete::{alphanumeric1, char, digit1, space0},
+    combinator::{map, map_res, opt, recognize, value},
+    error::{Error, ErrorKind},
+    multi::separated_list1,
+    sequence::{delimited, pair, preceded, terminated, tuple},
 };
 
-use crate::{rebuild_graph::PackageTarget, rebuild_reason::RebuildReason};
+use crate::{
+    rebuild_graph::PackageTarget,
+    rebuild_reason::{DependencyChangeContext, RebuildReason},
+};
 
 /// A parsed rebuild entry with package context and reason
 #[derive(Debug, Clone)]
 pub struct ParsedRebuildEntry {
     pub package: PackageTarget,
     pub reason: RebuildReason,
+    /// Time since the start of the cargo invocation, if the line had one
+    pub timestamp: Option<Duration>,
 }
 
 impl ParsedRebuildEntry {
     #[must_use]
-    pub const fn new(package: PackageTarget, reason: RebuildReason) -> Self {
-        Self { package, reason }
+    pub const fn new(
+        package: PackageTarget,
+        reason: RebuildReason,
+        timestamp: Option<Duration>,
+    ) -> Self {
+        Self {
+            package,
+            reason,
+            timestamp,
+        }
+    }
+
+    /// Record the fingerprint file of the unit in a dependency change's
+    /// context. Other reasons carry no context and are left unchanged.
+    #[must_use]
+    pub fn with_fingerprint_file(mut self, path: &str) -> Self {
+        if let RebuildReason::UnitDependencyInfoChanged {
+            context: Some(context),
+            ..
+        } = &mut self.reason
+        {
+            context.fingerprint_file = Some(path.to_string());
+        }
+        self
+    }
+
+    /// Turn a bare `FsStatusOutdated` reason, which names no file, into a
+    /// change of the file from the unit's `stale: changed` line. Stale
+    /// dependency fingerprints are left alone: their stale line is about the
+    /// dependency.
+    #[must_use]
+    pub fn with_stale_path(mut self, path: &str) -> Self {
+        if let RebuildReason::Unknown(raw) = &self.reason
+            && raw.starts_with("FsStatusOutdated")
+            && !raw.contains("StaleDep")
+        {
+            self.reason = RebuildReason::FileChanged {
+                path: path.to_string(),
+                reference: None,
+            };
+        }
+        self
     }
 }
 
-/// Extract package context from cargo log line
-/// Parses patterns like: `prepare_target{force=false package_id=libz-sys
-/// v1.1.23 target="build-script-build"}`
-fn extract_package_context(line: &str) -> PackageTarget {
-    let package_id = line.find("package_id=").map_or_else(
+/// What cargo logged about a unit on the lines before its `dirty:` line:
+/// the fingerprint file at debug level and the first `stale: changed` path
+#[derive(Debug, Clone, Default)]
+pub struct UnitHints {
+    package: Option<PackageTarget>,
+    fingerprint_file: Option<String>,
+    stale_path: Option<String>,
+}
+
+impl UnitHints {
+    /// Remember what `line` says about its unit. A line about another unit
+    /// discards the hints collected so far.
+    pub fn observe(&mut self, line: &str) {
+        if let Some((package, path)) = parse_fingerprint_location(line) {
+            self.for_unit(package).fingerprint_file = Some(path);
+        } else if let Some((package, path)) = parse_stale_change(line) {
+            self.for_unit(package).stale_path.get_or_insert(path);
+        }
+    }
+
+    fn for_unit(&mut self, package: PackageTarget) -> &mut Self {
+        if self.package.as_ref() != Some(&package) {
+            *self = Self {
+                package: Some(package),
+                ..Self::default()
+            };
+        }
+        self
+    }
+
+    /// Fill in the hints for an entry of the same unit
+    #[must_use]
+    pub fn apply(&self, mut entry: ParsedRebuildEntry) -> ParsedRebuildEntry {
+        if self.package.as_ref() != Some(&entry.package) {
+            return entry;
+        }
+        if let Some(path) = &self.fingerprint_file {
+            entry = entry.with_fingerprint_file(path);
+        }
+        if let Some(path) = &self.stale_path {
+            entry = entry.with_stale_path(path);
+        }
+        entry
+    }
+}
+
+/// Where and why parsing a `dirty:` line stopped
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseFailure {
+    /// Byte offset into the log line at which parsing failed
+    pub offset: usize,
+    pub kind: ErrorKind,
+    /// Name of the reason that was being parsed, such as `EnvVarChanged`
+    pub reason: Option<String>,
+}
+
+impl Display for ParseFailure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{} at byte {}", self.kind.description(), self.offset)?;
+        if let Some(reason) = &self.reason {
+            write!(f, " while parsing {reason}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Extract the unit a cargo fingerprint log line is about.
+///
+/// Expects the span fields cargo logs for `prepare_target`, e.g.
+/// `prepare_target{force=false package_id=libz-sys v1.1.23
+/// (registry+https://github.com/rust-lang/crates.io-index)
+/// target="build-script-build"}`. The `package_id` runs until ` target=` or
+/// the closing `}` and loses a trailing parenthesized source; `target` may be
+/// quoted or bare. Without `package_id` the id is `"unknown"`, and without
+/// `target` there is no target.
+#[must_use]
+pub fn parse_package_target(line: &str) -> PackageTarget {
+    // Split on the markers instead of slicing at computed offsets, so any
+    // UTF-8 around them is left intact
+    let package_id = line.split_once("package_id=").map_or_else(
         || "unknown".to_string(),
-        |pkg_start| {
-            let after_pkg = &line[pkg_start + 11..];
-            let end = after_pkg
-                .find(" target=")
-                .or_else(|| after_pkg.find('}'))
-                .unwrap_or(after_pkg.len());
-            after_pkg[..end].trim().to_string()
+        |(_, after_pkg)| {
+            let value = after_pkg
+                .split_once(" target=")
+                .or_else(|| after_pkg.split_once('}'))
+                .map_or(after_pkg, |(value, _)| value);
+            strip_package_source(value.trim()).to_string()
         },
     );
 
-    let target = line.find("target=").and_then(|target_start| {
-        let after_target = &line[target_start + 7..];
-
+    let target = line.split_once("target=").and_then(|(_, after_target)| {
         if let Some(stripped) = after_target.strip_prefix('"') {
-            return stripped
-                .find('"')
-                .map(|quote_end| stripped[..quote_end].to_string());
+            return stripped.split_once('"').map(|(value, _)| value.to_string());
         }
 
-        let end = after_target
-            .find([' ', '}', ':'])
-            .unwrap_or(after_target.len());
-        let value = after_target[..end].trim();
-
-        if value.is_empty() {
-            None
-        } else {
-            Some(value.to_string())
-        }
+        let value = after_target
+            .split([' ', '}', ':'])
+            .next()
+            .unwrap_or_default()
+            .trim();
+        (!value.is_empty()).then(|| value.to_string())
     });
 
     PackageTarget::new(package_id, target)
 }
 
-// Parse a quoted string: "hello world"
+/// Extract the unit and fingerprint file from a debug level line such as
+/// `prepare_target{...}: cargo::core::compiler::fingerprint: fingerprint at:
+/// /project/target/debug/.fingerprint/app-4d92/bin-app`. Cargo logs it just
+/// before the `dirty:` line of the same unit.
+#[must_use]
+pub fn parse_fingerprint_location(line: &str) -> Option<(PackageTarget, String)> {
+    let (_, path) = line.split_once("fingerprint at: ")?;
+    Some((parse_package_target(line), path.trim().to_string()))
+}
+
+/// Extract the unit and path from a line such as `prepare_target{...}:
+/// cargo::core::compiler::fingerprint: stale: changed "/project/src/lib.rs"`
+#[must_use]
+pub fn parse_stale_change(line: &str) -> Option<(PackageTarget, String)> {
+    let (_, rest) = line.split_once("stale: changed ")?;
+    let (_, path) = parse_quoted_string(rest).ok()?;
+    Some((parse_package_target(line), path))
+}
+
+/// Drop a trailing source such as `(registry+https://...)` or
+/// `(path+file://...)` from a package id, keeping the name and version
+fn strip_package_source(package_id: &str) -> &str {
+    match package_id.rfind(" (") {
+        Some(start) if package_id.ends_with(')') => package_id[..start].trim_end(),
+        _ => package_id,
+    }
+}
+
+/// Extract the package id from a cargo progress line such as
+/// `   Compiling libz-sys v1.1.23` or `    Checking app v0.1.0 (/src/app)`,
+/// without its source. `None` for any other line.
+#[must_use]
+pub fn parse_compiled_package(line: &str) -> Option<String> {
+    let rest = ["Compiling ", "Checking ", "Documenting "]
+        .iter()
+        .find_map(|verb| line.trim_start().strip_prefix(verb))?;
+    let package_id = strip_package_source(rest.trim());
+    package_id
+        .split_whitespace()
+        .nth(1)
+        .is_some_and(|version| version.starts_with('v'))
+        .then(|| package_id.to_string())
+}
+
+// Parse the leading log timestamp: "   0.102058909s"
+fn parse_timestamp(input: &str) -> IResult<&str, Duration> {
+    let (input, _) = space0(input)?;
+    let (input, secs) = map_res(digit1, str::parse::<u64>)(input)?;
+    let (input, _) = char('.')(input)?;
+    let (input, fraction) = digit1(input)?;
+    let (input, _) = char('s')(input)?;
+
+    // Scale the fraction to nanoseconds, ignoring digits beyond nanosecond
+    // precision
+    let nanos = fraction
+        .bytes()
+        .chain(repeat(b'0'))
+        .take(9)
+        .fold(0, |acc, digit| acc * 10 + u32::from(digit - b'0'));
+
+    Ok((input, Duration::new(secs, nanos)))
+}
+
+// Parse a quoted string as printed by Rust's Debug: "hello \"world\"",
+// unescaping the escape sequences in the returned value
 fn parse_quoted_string(input: &str) -> IResult<&str, String> {
     delimited(
         char('"'),
-        map(take_until("\""), |s: &str| s.to_string()),
+        map(
+            opt(escaped_transform(
+                is_not("\\\""),
+                '\\',
+                alt((
+                    value("\\", char('\\')),
+                    value("\"", char('"')),
+                    value("'", char('\'')),
+                    value("\n", char('n')),
+                    value("\r", char('r')),
+                    value("\t", char('t')),
+                    value("\0", char('0')),
+                )),
+            )),
+            Option::unwrap_or_default,
+        ),
         char('"'),
     )(input)
 }
 
-// Parse a number (used for fingerprints)
+// Parse a number (used for file times)
 fn parse_number(input: &str) -> IResult<&str, String> {
     map(digit1, |s: &str| s.to_string())(input)
 }
 
+// Parse a fingerprint: an opaque token, kept verbatim, with an optional sign
+fn parse_fingerprint(input: &str) -> IResult<&str, String> {
+    map(
+        recognize(pair(opt(char('-')), alphanumeric1)),
+        str::to_string,
+    )(input)
+}
+
 // Parse Option<T>: "Some(value)" or "None"
 fn parse_option_string(input: &str) -> IResult<&str, Option<String>> {
     alt((
@@ -101,23 +306,34 @@ fn parse_comma(input: &str) -> IResult<&str, ()> {
     map(tuple((space0, char(','), space0)), |_| ())(input)
 }
 
-// Parse EnvVarChanged { name: "CC", old_value: Some("gcc"), new_value: None }
-fn parse_env_var_changed(input: &str) -> IResult<&str, RebuildReason> {
-    let (input, _) = tag("EnvVarChanged")(input)?;
+// Parse the body of an env change: { name: "CC", old_value: Some("gcc"),
+// new_value: None }. Cargo's StaleItem::ChangedEnv names the same fields var,
+// previous and current.
+fn parse_env_change_fields(input: &str) -> IResult<&str, RebuildReason> {
     let (input, _) = tuple((space0, char('{'), space0))(input)?;
 
     // Parse name: "value"
-    let (input, _) = tuple((tag("name"), space0, char(':'), space0))(input)?;
+    let (input, _) = tuple((alt((tag("name"), tag("var"))), space0, char(':'), space0))(input)?;
     let (input, name) = parse_quoted_string(input)?;
     let (input, ()) = parse_comma(input)?;
 
     // Parse old_value: Option<String>
-    let (input, _) = tuple((tag("old_value"), space0, char(':'), space0))(input)?;
+    let (input, _) = tuple((
+        alt((tag("old_value"), tag("previous"))),
+        space0,
+        char(':'),
+        space0,
+    ))(input)?;
     let (input, old_value) = parse_option_string(input)?;
     let (input, ()) = parse_comma(input)?;
 
     // Parse new_value: Option<String>
-    let (input, _) = tuple((tag("new_value"), space0, char(':'), space0))(input)?;
+    let (input, _) = tuple((
+        alt((tag("new_value"), tag("current"))),
+        space0,
+        char(':'),
+        space0,
+    ))(input)?;
     let (input, new_value) = parse_option_string(input)?;
 
     let (input, _) = tuple((space0, char('}')))(input)?;
@@ -132,6 +348,12 @@ fn parse_env_var_changed(input: &str) -> IResult<&str, RebuildReason> {
     ))
 }
 
+// Parse EnvVarChanged { name: "CC", old_value: Some("gcc"), new_value: None }
+fn parse_env_var_changed(input: &str) -> IResult<&str, RebuildReason> {
+    let (input, _) = tag("EnvVarChanged")(input)?;
+    parse_env_change_fields(input)
+}
+
 // Parse UnitDependencyInfoChanged { old_name: "rusqlite", old_fingerprint: 123,
 // new_name: "rusqlite", new_fingerprint: 456 }
 fn parse_unit_dependency_info_changed(input: &str) -> IResult<&str, RebuildReason> {
@@ -145,7 +367,7 @@ fn parse_unit_dependency_info_changed(input: &str) -> IResult<&str, RebuildReaso
 
     // Parse old_fingerprint: number
     let (input, _) = tuple((tag("old_fingerprint"), space0, char(':'), space0))(input)?;
-    let (input, old_fingerprint) = parse_number(input)?;
+    let (input, old_fingerprint) = parse_fingerprint(input)?;
     let (input, ()) = parse_comma(input)?;
 
     // Parse new_name: "value" (but we don't need to store it)
@@ -155,7 +377,7 @@ fn parse_unit_dependency_info_changed(input: &str) -> IResult<&str, RebuildReaso
 
     // Parse new_fingerprint: number
     let (input, _) = tuple((tag("new_fingerprint"), space0, char(':'), space0))(input)?;
-    let (input, new_fingerprint) = parse_number(input)?;
+    let (input, new_fingerprint) = parse_fingerprint(input)?;
 
     let (input, _) = tuple((space0, char('}')))(input)?;
 
@@ -165,7 +387,8 @@ fn parse_unit_dependency_info_changed(input: &str) -> IResult<&str, RebuildReaso
             name: old_name,
             old_fingerprint,
             new_fingerprint,
-            context: None, // We'll enhance this with context parsing later
+            // Filled in from the log line by `with_dependency_context`
+            context: None,
         },
     ))
 }
@@ -229,11 +452,38 @@ fn parse_rustflags_changed(input: &str) -> IResult<&str, RebuildReason> {
     Ok((input, RebuildReason::RustflagsChanged { old, new }))
 }
 
+// Parse a feature list, either quoted ("default, serde") or bare (default,
+// serde) up to the given terminator
+fn parse_feature_list<'a>(
+    terminator: &'static str,
+) -> impl FnMut(&'a str) -> IResult<&'a str, String> {
+    alt((
+        parse_quoted_string,
+        map(take_until(terminator), |s: &str| s.trim().to_string()),
+    ))
+}
+
 // Parse FeaturesChanged { old: "...", new: "..." }
 fn parse_features_changed(input: &str) -> IResult<&str, RebuildReason> {
     let (input, _) = tag("FeaturesChanged")(input)?;
     let (input, _) = tuple((space0, char('{'), space0))(input)?;
 
+    let (input, _) = tuple((tag("old"), space0, char(':'), space0))(input)?;
+    let (input, old) = parse_feature_list(", new")(input)?;
+    let (input, ()) = parse_comma(input)?;
+
+    let (input, _) = tuple((tag("new"), space0, char(':'), space0))(input)?;
+    let (input, new) = parse_feature_list("}")(input)?;
+
+    let (input, _) = tuple((space0, char('}')))(input)?;
+
+    Ok((input, RebuildReason::FeaturesChanged { old, new }))
+}
+
+// Parse the body { old: "...", new: "..." } shared by several reasons
+fn parse_quoted_old_new(input: &str) -> IResult<&str, (String, String)> {
+    let (input, _) = tuple((space0, char('{'), space0))(input)?;
+
     let (input, _) = tuple((tag("old"), space0, char(':'), space0))(input)?;
     let (input, old) = parse_quoted_string(input)?;
     let (input, ()) = parse_comma(input)?;
@@ -243,7 +493,43 @@ fn parse_features_changed(input: &str) -> IResult<&str, RebuildReason> {
 
     let (input, _) = tuple((space0, char('}')))(input)?;
 
-    Ok((input, RebuildReason::FeaturesChanged { old, new }))
+    Ok((input, (old, new)))
+}
+
+// Parse DepInfoOutputChanged { old: "...", new: "..." }
+fn parse_dep_info_output_changed(input: &str) -> IResult<&str, RebuildReason> {
+    let (input, _) = tag("DepInfoOutputChanged")(input)?;
+    let (input, (old, new)) = parse_quoted_old_new(input)?;
+    Ok((input, RebuildReason::DepInfoOutputChanged { old, new }))
+}
+
+// Parse PrecalculatedComponentsChanged { old: "...", new: "..." }
+fn parse_precalculated_components_changed(input: &str) -> IResult<&str, RebuildReason> {
+    let (input, _) = tag("PrecalculatedComponentsChanged")(input)?;
+    let (input, (old, new)) = parse_quoted_old_new(input)?;
+    Ok((
+        input,
+        RebuildReason::PrecalculatedComponentsChanged { old, new },
+    ))
+}
+
+// Parse LocalFingerprintTypeChanged { old: "...", new: "..." }
+fn parse_local_fingerprint_type_changed(input: &str) -> IResult<&str, RebuildReason> {
+    let (input, _) = tag("LocalFingerprintTypeChanged")(input)?;
+    let (input, (old, new)) = parse_quoted_old_new(input)?;
+    Ok((
+        input,
+        RebuildReason::LocalFingerprintTypeChanged { old, new },
+    ))
+}
+
+// Parse RustcChanged, with the versions when cargo logs them:
+// RustcChanged { old: "...", new: "..." }
+fn parse_rustc_changed(input: &str) -> IResult<&str, RebuildReason> {
+    let (input, _) = tag("RustcChanged")(input)?;
+    let (input, versions) = opt(parse_quoted_old_new)(input)?;
+    let (old, new) = versions.unzip();
+    Ok((input, RebuildReason::CompilerChanged { old, new }))
 }
 
 // Parse FileTime { seconds: 123, nanos: 456 }
@@ -267,13 +553,13 @@ fn parse_file_time(input: &str) -> IResult<&str, (String, String)> {
 
 // Parse ChangedFile { reference: "...", reference_mtime: FileTime { ... },
 // stale: "...", stale_mtime: FileTime { ... } }
-fn parse_changed_file(input: &str) -> IResult<&str, String> {
+fn parse_changed_file(input: &str) -> IResult<&str, RebuildReason> {
     let (input, _) = tag("ChangedFile")(input)?;
     let (input, _) = tuple((space0, char('{'), space0))(input)?;
 
-    // Skip reference field
+    // Extract reference path
     let (input, _) = tuple((tag("reference"), space0, char(':'), space0))(input)?;
-    let (input, _) = parse_quoted_string(input)?;
+    let (input, reference) = parse_quoted_string(input)?;
     let (input, ()) = parse_comma(input)?;
 
     // Skip reference_mtime field
@@ -292,19 +578,55 @@ fn parse_changed_file(input: &str) -> IResult<&str, String> {
 
     let (input, _) = tuple((space0, char('}')))(input)?;
 
-    Ok((input, stale_path))
+    Ok((
+        input,
+        RebuildReason::FileChanged {
+            path: stale_path,
+            reference: Some(reference),
+        },
+    ))
 }
 
-// Parse FsStatusOutdated(StaleItem(ChangedFile { ... }))
-fn parse_fs_status_outdated_changed_file(input: &str) -> IResult<&str, RebuildReason> {
-    let (input, _) = tag("FsStatusOutdated")(input)?;
-    let (input, _) = tuple((char('('), tag("StaleItem"), char('(')))(input)?;
+type Parser<T> = fn(&str) -> IResult<&str, T>;
+type ReasonParser = Parser<RebuildReason>;
+
+// Parse MissingFile("...")
+fn parse_missing_file(input: &str) -> IResult<&str, RebuildReason> {
+    map(
+        delimited(
+            tuple((tag("MissingFile"), char('('))),
+            parse_quoted_string,
+            char(')'),
+        ),
+        |path| RebuildReason::MissingFile { path },
+    )(input)
+}
 
-    let (input, path) = parse_changed_file(input)?;
+// Parse ChangedEnv { var: "...", previous: ..., current: ... }
+fn parse_changed_env(input: &str) -> IResult<&str, RebuildReason> {
+    preceded(tag("ChangedEnv"), parse_env_change_fields)(input)
+}
+
+const STALE_ITEM_PARSERS: &[ReasonParser] =
+    &[parse_changed_file, parse_missing_file, parse_changed_env];
 
-    let (input, _) = tuple((char(')'), char(')')))(input)?;
+// Parse StaleItem(...) wrapping a changed file, missing file or changed
+// environment variable
+fn parse_stale_item(input: &str) -> IResult<&str, RebuildReason> {
+    let (input, _) = tuple((tag("StaleItem"), char('(')))(input)?;
+    let (input, reason) = parse_furthest(input, STALE_ITEM_PARSERS)?;
+    let (input, _) = char(')')(input)?;
+    Ok((input, reason))
+}
 
-    Ok((input, RebuildReason::FileChanged { path }))
+// Parse FsStatusOutdated(StaleItem(...), StaleItem(...), ...), which lists
+// every stale item of the unit
+fn parse_fs_status_outdated_stale_items(input: &str) -> IResult<&str, Vec<RebuildReason>> {
+    delimited(
+        tuple((tag("FsStatusOutdated"), char('('))),
+        separated_list1(tuple((char(','), space0)), parse_stale_item),
+        char(')'),
+    )(input)
 }
 
 // Parse FsStatusOutdated(StaleDepFingerprint { name: "..." })
@@ -334,80 +656,214 @@ fn parse_fs_status_outdated_stale_dep(input: &str) -> IResult<&str, RebuildReaso
     ))
 }
 
-// Main parser for dirty reasons
+// All known dirty reason formats, tried in order
+const REASON_PARSERS: &[ReasonParser] = &[
+    parse_env_var_changed,
+    parse_unit_dependency_info_changed,
+    parse_target_configuration_changed,
+    parse_profile_configuration_changed,
+    parse_rustflags_changed,
+    parse_features_changed,
+    parse_dep_info_output_changed,
+    parse_precalculated_components_changed,
+    parse_local_fingerprint_type_changed,
+    parse_rustc_changed,
+    parse_fs_status_outdated_stale_dep,
+];
+
+// Try each parser in turn. Unlike `alt`, which reports the error of the last
+// alternative, this keeps the error of the parser that got furthest so
+// failures point at the offending token.
+fn parse_furthest<'a, T>(input: &'a str, parsers: &[Parser<T>]) -> IResult<&'a str, T> {
+    let mut furthest: Option<Error<&str>> = None;
+
+    for parser in parsers {
+        match parser(input) {
+            Err(nom::Err::Error(e)) => {
+                if furthest
+                    .as_ref()
+                    .is_none_or(|f| e.input.len() < f.input.len())
+                {
+                    furthest = Some(e);
+                }
+            }
+            result => return result,
+        }
+    }
+
+    Err(nom::Err::Error(
+        furthest.unwrap_or_else(|| Error::new(input, ErrorKind::Alt)),
+    ))
+}
+
+// Main parser for a single dirty reason
 fn parse_dirty_reason_content(input: &str) -> IResult<&str, RebuildReason> {
-    alt((
-        parse_env_var_changed,
-        parse_unit_dependency_info_changed,
-        parse_target_configuration_changed,
-        parse_profile_configuration_changed,
-        parse_rustflags_changed,
-        parse_features_changed,
-        parse_fs_status_outdated_stale_dep,
-        parse_fs_status_outdated_changed_file,
-        parse_unknown_reason,
-    ))(input)
+    parse_furthest(input, REASON_PARSERS)
+}
+
+// Parse the reasons of a dirty line. Only FsStatusOutdated can list several
+// stale items; every other reason yields exactly one.
+fn parse_dirty_reason_contents(input: &str) -> IResult<&str, Vec<RebuildReason>> {
+    const PARSERS: &[Parser<Vec<RebuildReason>>] =
+        &[parse_fs_status_outdated_stale_items, |input| {
+            map(parse_dirty_reason_content, |reason| vec![reason])(input)
+        }];
+    parse_furthest(input, PARSERS)
 }
 
-// Fallback parser for unknown/unrecognized dirty reasons
-#[allow(clippy::unnecessary_wraps, reason = "Needed for nom parser combinator")]
-fn parse_unknown_reason(input: &str) -> IResult<&str, RebuildReason> {
-    let content = input.trim().to_string();
-    Ok(("", RebuildReason::Unknown(content)))
+/// Parse the "dirty: <reason>" pattern, reporting where parsing stopped
+pub fn parse_rebuild_reason_verbose(input: &str) -> Result<Vec<RebuildReason>, ParseFailure> {
+    let to_failure = |e: nom::Err<Error<&str>>| match e {
+        nom::Err::Error(e) | nom::Err::Failure(e) => ParseFailure {
+            offset: input.len() - e.input.len(),
+            kind: e.code,
+            reason: None,
+        },
+        nom::Err::Incomplete(_) => ParseFailure {
+            offset: input.len(),
+            kind: ErrorKind::Eof,
+            reason: None,
+        },
+    };
+
+    let (rest, _) =
+        tuple((take_until("dirty:"), tag("dirty:"), space0))(input).map_err(to_failure)?;
+    let (_, reasons) = parse_dirty_reason_contents(rest).map_err(|e| {
+        let attempted = rest
+            .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .next()
+            .filter(|name| !name.is_empty());
+        ParseFailure {
+            reason: attempted.map(str::to_string),
+            ..to_failure(e)
+        }
+    })?;
+    Ok(reasons)
 }
 
 // Parse the full "dirty: <reason>" pattern
 #[must_use]
-pub fn parse_rebuild_reason(input: &str) -> Option<RebuildReason> {
+pub fn parse_rebuild_reasons(input: &str) -> Option<Vec<RebuildReason>> {
     // Only parse "dirty:" lines - the "stale: changed" lines are redundant
-    // with FsStatusOutdated(StaleItem(ChangedFile...)) and report the wrong package
-    // context
-    input.find("dirty:").and_then(|dirty_start| {
-        let dirty_content = &input[dirty_start + 6..].trim_start();
-
-        match parse_dirty_reason_content(dirty_content) {
-            Ok((_, reason)) => Some(reason),
-            Err(_) => None,
-        }
+    // with FsStatusOutdated(StaleItem(ChangedFile...)) and may name a file of a
+    // dependency. `UnitHints` uses them only when the dirty line has no path.
+    input.find("dirty:").map(|dirty_start| {
+        // Fall back to the raw content for unknown/unrecognized dirty reasons
+        parse_rebuild_reason_verbose(input).unwrap_or_else(|_| {
+            vec![RebuildReason::Unknown(
+                input[dirty_start + 6..].trim().to_string(),
+            )]
+        })
     })
 }
 
-/// Parse a complete rebuild entry with package context from a cargo log line
+/// Parse the rebuild entries with package context from a cargo log line, one
+/// per reason. Empty if the line has no `dirty:` marker.
 #[must_use]
-pub fn parse_rebuild_entry(input: &str) -> Option<ParsedRebuildEntry> {
-    let reason = parse_rebuild_reason(input)?;
-    let package = extract_package_context(input);
-    Some(ParsedRebuildEntry::new(package, reason))
+pub fn parse_rebuild_entry(input: &str) -> Vec<ParsedRebuildEntry> {
+    let Some(reasons) = parse_rebuild_reasons(input) else {
+        return Vec::new();
+    };
+    let package = parse_package_target(input);
+    let timestamp = parse_timestamp(input).ok().map(|(_, timestamp)| timestamp);
+    reasons
+        .into_iter()
+        .map(|reason| with_dependency_context(reason, &package))
+        .map(|reason| ParsedRebuildEntry::new(package.clone(), reason, timestamp))
+        .collect()
+}
+
+// Describe the unit that was rebuilt because of a dependency, using the package
+// context of the log line
+fn with_dependency_context(reason: RebuildReason, package: &PackageTarget) -> RebuildReason {
+    match reason {
+        RebuildReason::UnitDependencyInfoChanged {
+            name,
+            old_fingerprint,
+            new_fingerprint,
+            context: None,
+        } => RebuildReason::UnitDependencyInfoChanged {
+            name,
+            old_fingerprint,
+            new_fingerprint,
+            context: Some(DependencyChangeContext {
+                package_id: Some(package.package_id.clone()).filter(|id| id != "unknown"),
+                target_type: package.target.clone(),
+                root_cause: None,
+                fingerprint_file: None,
+            }),
+        },
+        reason => reason,
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    // The first reason of a dirty line
+    fn parse_rebuild_reason(input: &str) -> Option<RebuildReason> {
+        parse_rebuild_reasons(input).and_then(|reasons| reasons.into_iter().next())
+    }
+
     #[test]
     fn extracts_package_context_from_cargo_log() {
         let log_line = r#"    0.102058909s  INFO prepare_target{force=false package_id=libz-sys v1.1.23 target="build-script-build"}: cargo::core::compiler::fingerprint:     dirty: EnvVarChanged { name: "CC", old_value: Some("gcc"), new_value: None }"#;
 
-        let entry = parse_rebuild_entry(log_line).unwrap();
+        let entry = parse_rebuild_entry(log_line).remove(0);
         assert_eq!(entry.package.package_id, "libz-sys v1.1.23");
         assert_eq!(entry.package.target, Some("build-script-build".to_string()));
     }
 
+    #[test]
+    fn extracts_package_context_around_non_ascii_text() {
+        let lines = [
+            r#"0.1s  INFO prepare_target{force=false package_id=café v0.1.0 target="crème"}: dirty: EnvVarChanged { name: "CC", old_value: Some("gcc"), new_value: None }"#,
+            r#"0.1s  INFO prepare_target{force=false package_id=€ target=ü}: dirty: EnvVarChanged { name: "CC", old_value: Some("gcc"), new_value: None }"#,
+            "package_id=é",
+            "target=\u{1F980}",
+        ];
+
+        let packages: Vec<_> = lines
+            .iter()
+            .map(|line| parse_package_target(line))
+            .collect();
+
+        assert_eq!(
+            packages,
+            [
+                PackageTarget::new("café v0.1.0", Some("crème".to_string())),
+                PackageTarget::new("€", Some("ü".to_string())),
+                PackageTarget::new("é", None),
+                PackageTarget::new("unknown", Some("\u{1F980}".to_string())),
+            ]
+        );
+    }
+
     #[test]
     fn handles_missing_package_context() {
         let log_line =
             r#"dirty: EnvVarChanged { name: "CC", old_value: Some("gcc"), new_value: None }"#;
 
-        let entry = parse_rebuild_entry(log_line).unwrap();
+        let entry = parse_rebuild_entry(log_line).remove(0);
         assert_eq!(entry.package.package_id, "unknown");
         assert_eq!(entry.package.target, None);
+        assert_eq!(entry.timestamp, None);
+    }
+
+    #[test]
+    fn extracts_timestamp_from_cargo_log() {
+        let log_line = r#"    0.102058909s  INFO prepare_target{force=false package_id=libz-sys v1.1.23 target="build-script-build"}: cargo::core::compiler::fingerprint:     dirty: EnvVarChanged { name: "CC", old_value: Some("gcc"), new_value: None }"#;
+
+        let entry = parse_rebuild_entry(log_line).remove(0);
+        assert_eq!(entry.timestamp, Some(Duration::new(0, 102_058_909)));
     }
 
     #[test]
     fn extracts_package_without_target() {
         let log_line = r"prepare_target{force=false package_id=serde v1.0.0}: dirty: TargetConfigurationChanged";
 
-        let entry = parse_rebuild_entry(log_line).unwrap();
+        let entry = parse_rebuild_entry(log_line).remove(0);
         assert_eq!(entry.package.package_id, "serde v1.0.0");
         assert_eq!(entry.package.target, None);
     }
@@ -442,6 +898,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn handles_escaped_quotes_and_backslashes() {
+        let log_line = r#"dirty: EnvVarChanged { name: "TOOL", old_value: Some("C:\\Program Files\\\"weird\""), new_value: Some("") }"#;
+        let result = parse_rebuild_reason(log_line);
+
+        assert_eq!(
+            result,
+            Some(RebuildReason::EnvVarChanged {
+                name: "TOOL".to_string(),
+                old_value: Some(r#"C:\Program Files\"weird""#.to_string()),
+                new_value: Some(String::new()),
+            })
+        );
+    }
+
     #[test]
     fn handles_unit_dependency_info_changed() {
         let log_line = r#"dirty: UnitDependencyInfoChanged { old_name: "rusqlite", old_fingerprint: 5920731552898212716, new_name: "rusqlite", new_fingerprint: 7766129310588964256 }"#;
@@ -475,6 +946,39 @@ mod tests {
             result,
             Some(RebuildReason::FileChanged {
                 path: "/tmp/.tmp6t5LHE/src/main.rs".to_string(),
+                reference: Some(
+                    "/tmp/.tmp6t5LHE/target/debug/.fingerprint/target-test-d08e845c3c592b51/\
+                     dep-bin-target-test"
+                        .to_string()
+                ),
+            })
+        );
+    }
+
+    #[test]
+    fn handles_fs_status_outdated_with_missing_file() {
+        let log_line = r#"dirty: FsStatusOutdated(StaleItem(MissingFile("/tmp/x/foo.rlib")))"#;
+        let result = parse_rebuild_reason(log_line);
+
+        assert_eq!(
+            result,
+            Some(RebuildReason::MissingFile {
+                path: "/tmp/x/foo.rlib".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn handles_fs_status_outdated_with_changed_env() {
+        let log_line = r#"    1.204587311s  INFO prepare_target{force=false package_id=openssl-sys v0.9.103 target="build-script-build"}: cargo::core::compiler::fingerprint:     dirty: FsStatusOutdated(StaleItem(ChangedEnv { var: "OPENSSL_DIR", previous: None, current: Some("/opt/openssl") }))"#;
+        let result = parse_rebuild_reason(log_line);
+
+        assert_eq!(
+            result,
+            Some(RebuildReason::EnvVarChanged {
+                name: "OPENSSL_DIR".to_string(),
+                old_value: None,
+                new_value: Some("/opt/openssl".to_string()),
             })
         );
     }
@@ -492,6 +996,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn captures_unknown_reason_from_full_log_line() {
+        let log_line = r#"    0.120000000s  INFO prepare_target{force=false package_id=foo v0.1.0 target="foo"}: cargo::core::compiler::fingerprint:     dirty: SomeFutureReason { data: "x" }"#;
+
+        let entry = parse_rebuild_entry(log_line).remove(0);
+        assert_eq!(entry.package.package_id, "foo v0.1.0");
+        assert_eq!(
+            entry.reason,
+            RebuildReason::Unknown(r#"SomeFutureReason { data: "x" }"#.to_string())
+        );
+    }
+
     #[test]
     fn handles_rustflags_changed() {
         let log_line = r#"dirty: RustflagsChanged { old: ["--cfg", "test"], new: ["--cfg", "test", "-C", "target-cpu=native"] }"#;
@@ -539,6 +1055,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn handles_features_changed_with_bare_values() {
+        let log_line = r"dirty: FeaturesChanged { old: default, new: default,serde }";
+        let result = parse_rebuild_reason(log_line);
+
+        assert_eq!(
+            result,
+            Some(RebuildReason::FeaturesChanged {
+                old: "default".to_string(),
+                new: "default,serde".to_string(),
+            })
+        );
+    }
+
     #[test]
     fn handles_profile_configuration_changed() {
         let log_line = r"dirty: ProfileConfigurationChanged";
@@ -547,6 +1077,46 @@ mod tests {
         assert_eq!(result, Some(RebuildReason::ProfileConfigurationChanged));
     }
 
+    #[test]
+    fn handles_profile_configuration_changed_with_package_context() {
+        let log_line = r#"    0.073411020s  INFO prepare_target{force=false package_id=app v0.1.0 target="app"}: cargo::core::compiler::fingerprint:     dirty: ProfileConfigurationChanged"#;
+
+        let entry = parse_rebuild_entry(log_line).remove(0);
+        assert_eq!(entry.package.package_id, "app v0.1.0");
+        assert_eq!(entry.reason, RebuildReason::ProfileConfigurationChanged);
+    }
+
+    #[test]
+    fn reports_position_of_parse_failure() {
+        let log_line =
+            r#"dirty: EnvVarChanged { name: CC", old_value: Some("gcc"), new_value: None }"#;
+        let failure = parse_rebuild_reason_verbose(log_line).unwrap_err();
+
+        assert_eq!(
+            failure.offset,
+            log_line.find("CC\"").unwrap(),
+            "Failure should point at the unquoted name"
+        );
+        assert_eq!(failure.kind, ErrorKind::Char);
+    }
+
+    #[test]
+    fn reports_attempted_reason_of_truncated_line() {
+        let log_line = r#"    0.1s  INFO cargo::core::compiler::fingerprint:     dirty: EnvVarChanged { name: "CC", old_value: Some("gcc"), new_va"#;
+        let failure = parse_rebuild_reason_verbose(log_line).unwrap_err();
+
+        assert_eq!(
+            failure.offset,
+            log_line.find("new_va").unwrap(),
+            "Failure should point at the truncated field"
+        );
+        assert_eq!(failure.reason.as_deref(), Some("EnvVarChanged"));
+        assert!(
+            failure.to_string().ends_with("while parsing EnvVarChanged"),
+            "Diagnostic should name the reason: {failure}"
+        );
+    }
+
     #[test]
     fn returns_none_for_lines_without_dirty_marker() {
         let log_line =
@@ -583,4 +1153,235 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn parses_every_stale_item_of_fs_status_outdated() {
+        let log_line = r#"dirty: FsStatusOutdated(StaleItem(ChangedFile { reference: "/t/dep-lib", reference_mtime: FileTime { seconds: 1, nanos: 0 }, stale: "/p/src/lib.rs", stale_mtime: FileTime { seconds: 2, nanos: 0 } }), StaleItem(MissingFile("/p/src/gone.rs")))"#;
+
+        let reasons = parse_rebuild_reasons(log_line).unwrap();
+
+        assert_eq!(
+            reasons,
+            [
+                RebuildReason::FileChanged {
+                    path: "/p/src/lib.rs".to_string(),
+                    reference: Some("/t/dep-lib".to_string()),
+                },
+                RebuildReason::MissingFile {
+                    path: "/p/src/gone.rs".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn fills_dependency_context_from_log_line() {
+        let log_line = r#"    0.215830470s  INFO prepare_target{force=false package_id=rusqlite v0.31.0 (registry+https://github.com/rust-lang/crates.io-index) target="rusqlite"}: cargo::core::compiler::fingerprint:     dirty: UnitDependencyInfoChanged { old_name: "libsqlite3_sys", old_fingerprint: 3226094009085469434, new_name: "libsqlite3_sys", new_fingerprint: 17151236291452738470 }"#;
+
+        let entry = parse_rebuild_entry(log_line).remove(0);
+
+        let RebuildReason::UnitDependencyInfoChanged { context, .. } = &entry.reason else {
+            panic!("Expected a dependency change, got {:?}", entry.reason);
+        };
+        assert_eq!(
+            context.as_ref(),
+            Some(&DependencyChangeContext {
+                package_id: Some("rusqlite v0.31.0".to_string()),
+                target_type: Some("rusqlite".to_string()),
+                root_cause: None,
+                fingerprint_file: None,
+            })
+        );
+        assert!(
+            entry.reason.explanation().contains("Target: rusqlite"),
+            "Explanation should show the target"
+        );
+    }
+
+    #[test]
+    fn strips_source_suffix_from_package_ids() {
+        let cases = [
+            (
+                r#"    0.1s  INFO prepare_target{force=false package_id=serde v1.0.0 (registry+https://github.com/rust-lang/crates.io-index) target="serde"}: cargo::core::compiler::fingerprint:     dirty: ProfileConfigurationChanged"#,
+                "serde",
+                "v1.0.0",
+            ),
+            (
+                r"    0.1s  INFO prepare_target{force=false package_id=lib-a v0.1.0 (path+file:///home/user/work/lib-a)}: cargo::core::compiler::fingerprint:     dirty: ProfileConfigurationChanged",
+                "lib-a",
+                "v0.1.0",
+            ),
+        ];
+
+        for (log_line, name, version) in cases {
+            let entry = parse_rebuild_entry(log_line).remove(0);
+            assert_eq!(entry.package.package_id, format!("{name} {version}"));
+            assert_eq!(entry.package.name(), name);
+            assert_eq!(entry.package.version(), Some(version));
+        }
+    }
+
+    #[test]
+    fn parses_dep_info_output_changed() {
+        let log_line = r#"    0.1s  INFO prepare_target{force=false package_id=app v0.1.0 target="app"}: cargo::core::compiler::fingerprint:     dirty: DepInfoOutputChanged { old: "/home/user/old/target/debug/.fingerprint/app-1/dep-bin-app", new: "/home/user/new/target/debug/.fingerprint/app-1/dep-bin-app" }"#;
+
+        let reason = parse_rebuild_reason(log_line).unwrap();
+
+        assert_eq!(
+            reason,
+            RebuildReason::DepInfoOutputChanged {
+                old: "/home/user/old/target/debug/.fingerprint/app-1/dep-bin-app".to_string(),
+                new: "/home/user/new/target/debug/.fingerprint/app-1/dep-bin-app".to_string(),
+            }
+        );
+        assert!(
+            reason.explanation().contains("project directory was moved"),
+            "Explanation should mention a moved directory"
+        );
+    }
+
+    #[test]
+    fn parses_precalculated_components_changed() {
+        let log_line = r#"    0.1s  INFO prepare_target{force=false package_id=app v0.1.0 target="app"}: cargo::core::compiler::fingerprint:     dirty: PrecalculatedComponentsChanged { old: "1.88.0", new: "1.89.0" }"#;
+
+        assert_eq!(
+            parse_rebuild_reason(log_line),
+            Some(RebuildReason::PrecalculatedComponentsChanged {
+                old: "1.88.0".to_string(),
+                new: "1.89.0".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn parses_local_fingerprint_type_changed() {
+        let log_line = r#"    0.1s  INFO prepare_target{force=false package_id=openssl-sys v0.9.103 target="build-script-build"}: cargo::core::compiler::fingerprint:     dirty: LocalFingerprintTypeChanged { old: "precalculated", new: "rerun-if-changed" }"#;
+
+        let reason = parse_rebuild_reason(log_line).unwrap();
+        assert_eq!(
+            reason,
+            RebuildReason::LocalFingerprintTypeChanged {
+                old: "precalculated".to_string(),
+                new: "rerun-if-changed".to_string(),
+            }
+        );
+        let explanation = reason.explanation();
+        assert!(
+            explanation.contains("cargo version") && explanation.contains("toolchain"),
+            "Explanation should point at the toolchain: {explanation}"
+        );
+    }
+
+    #[test]
+    fn parses_rustc_changed() {
+        let log_line = r#"    0.1s  INFO prepare_target{force=false package_id=serde v1.0.219 target="serde"}: cargo::core::compiler::fingerprint:     dirty: RustcChanged"#;
+
+        let reason = parse_rebuild_reason(log_line).unwrap();
+        assert_eq!(
+            reason,
+            RebuildReason::CompilerChanged {
+                old: None,
+                new: None
+            }
+        );
+        let explanation = reason.explanation();
+        assert!(
+            explanation.contains("rustup") && explanation.contains("rust-toolchain.toml"),
+            "Explanation should point at a toolchain switch: {explanation}"
+        );
+    }
+
+    #[test]
+    fn keeps_unusual_fingerprints_verbatim() {
+        for (old, new) in [
+            ("-8446744073709551616", "42"),
+            ("340282366920938463463374607431768211455", "0x1f"),
+        ] {
+            let log_line = format!(
+                r#"    0.1s  INFO prepare_target{{force=false package_id=app v0.1.0 target="app"}}: cargo::core::compiler::fingerprint:     dirty: UnitDependencyInfoChanged {{ old_name: "lib_a", old_fingerprint: {old}, new_name: "lib_a", new_fingerprint: {new} }}"#
+            );
+
+            let Some(RebuildReason::UnitDependencyInfoChanged {
+                old_fingerprint,
+                new_fingerprint,
+                ..
+            }) = parse_rebuild_reason(&log_line)
+            else {
+                panic!("Expected a dependency change in: {log_line}");
+            };
+            assert_eq!(
+                (old_fingerprint.as_str(), new_fingerprint.as_str()),
+                (old, new)
+            );
+        }
+    }
+
+    #[test]
+    fn parses_compiled_package_from_progress_lines() {
+        assert_eq!(
+            parse_compiled_package("   Compiling libz-sys v1.1.23").as_deref(),
+            Some("libz-sys v1.1.23")
+        );
+        assert_eq!(
+            parse_compiled_package("    Checking app v0.1.0 (/src/app)").as_deref(),
+            Some("app v0.1.0")
+        );
+        assert_eq!(
+            parse_compiled_package("    Finished `dev` profile in 0.10s"),
+            None
+        );
+        assert_eq!(parse_compiled_package("Compiling the world"), None);
+    }
+
+    #[test]
+    fn attaches_debug_fingerprint_location_to_dependency_changes() {
+        let location = r#"   0.010812058s DEBUG prepare_target{force=false package_id=app v0.1.0 (/tmp/ws/app) target="app"}: cargo::core::compiler::fingerprint: fingerprint at: /tmp/ws/target/debug/.fingerprint/app-4d9221d8f2317207/bin-app"#;
+        let dirty = r#"   0.010993042s  INFO prepare_target{force=false package_id=app v0.1.0 (/tmp/ws/app) target="app"}: cargo::core::compiler::fingerprint:     dirty: FsStatusOutdated(StaleDepFingerprint { name: "lib_a" })"#;
+
+        let (package, path) = parse_fingerprint_location(location).unwrap();
+        let entry = parse_rebuild_entry(dirty).remove(0);
+        assert_eq!(package, entry.package);
+
+        let entry = entry.with_fingerprint_file(&path);
+        let RebuildReason::UnitDependencyInfoChanged {
+            context: Some(context),
+            ..
+        } = &entry.reason
+        else {
+            panic!("Expected a dependency change, got {:?}", entry.reason);
+        };
+        assert_eq!(
+            context.fingerprint_file.as_deref(),
+            Some("/tmp/ws/target/debug/.fingerprint/app-4d9221d8f2317207/bin-app")
+        );
+        assert!(
+            entry
+                .reason
+                .explanation()
+                .contains("Fingerprint file: /tmp/ws/target")
+        );
+        assert_eq!(parse_fingerprint_location(dirty), None);
+    }
+
+    #[test]
+    fn reconstructs_changed_file_from_stale_line() {
+        let stale = r#"   0.010920475s  INFO prepare_target{force=false package_id=lib-a v0.1.0 (/ws/lib-a) target="lib_a"}: cargo::core::compiler::fingerprint: stale: changed "/ws/lib-a/src/lib.rs""#;
+        let dirty = r#"   0.010993042s  INFO prepare_target{force=false package_id=lib-a v0.1.0 (/ws/lib-a) target="lib_a"}: cargo::core::compiler::fingerprint:     dirty: FsStatusOutdated"#;
+
+        let mut hints = UnitHints::default();
+        hints.observe(stale);
+        let entry = hints.apply(parse_rebuild_entry(dirty).remove(0));
+
+        assert_eq!(
+            entry.reason,
+            RebuildReason::FileChanged {
+                path: "/ws/lib-a/src/lib.rs".to_string(),
+                reference: None,
+            }
+        );
+
+        let dependency = r#"   0.011s  INFO prepare_target{force=false package_id=lib-a v0.1.0 (/ws/lib-a) target="lib_a"}: cargo::core::compiler::fingerprint:     dirty: FsStatusOutdated(StaleDepFingerprint { unit: UnitIndex(2) })"#;
+        let entry = hints.apply(parse_rebuild_entry(dependency).remove(0));
+        assert!(matches!(entry.reason, RebuildReason::Unknown(_)));
+    }
 }
@@ -4,19 +4,80 @@
 //! logs and providing detailed explanations and actionable suggestions.
 
 use core::error::Error;
-use std::{fmt, io, path::PathBuf};
+use std::{fmt, io, path::PathBuf, time::Duration};
 
+mod cargo_messages;
 mod dirty_analyzer;
+mod file_watcher;
 mod fingerprint_parser;
+mod rebuild_analysis;
 mod rebuild_graph;
 mod rebuild_reason;
+mod rebuild_reporter;
+#[cfg(feature = "tui")]
+mod tui;
 
 pub use dirty_analyzer::Config;
+pub use fingerprint_parser::{ParsedRebuildEntry, parse_package_target, parse_rebuild_entry};
+pub use rebuild_graph::{
+    ImpactFraction, PackageTarget, RebuildGraph, RebuildNode, RootCauseChain, TargetKind,
+    TargetKinds,
+};
+pub use rebuild_reason::{DependencyChangeContext, RebuildReason, Severity};
+
+/// Build a rebuild graph from lines of a cargo log captured with
+/// `CARGO_LOG=cargo::core::compiler::fingerprint=info`. Lines without a
+/// rebuild reason are skipped.
+///
+/// ```
+/// let log = [
+///     r#"    0.010000000s  INFO prepare_target{force=false package_id=libz-sys v1.1.23 target="build-script-build"}: cargo::core::compiler::fingerprint:     dirty: EnvVarChanged { name: "CC", old_value: None, new_value: Some("clang") }"#,
+///     r#"    0.020000000s  INFO prepare_target{force=false package_id=rusqlite v0.31.0 target="rusqlite"}: cargo::core::compiler::fingerprint:     dirty: UnitDependencyInfoChanged { old_name: "libz_sys", old_fingerprint: 1, new_name: "libz_sys", new_fingerprint: 2 }"#,
+///     "   Compiling rusqlite v0.31.0",
+/// ];
+///
+/// let graph = cargo_frequent::analyze_log_lines(log.map(String::from));
+///
+/// let chains = graph.root_cause_chains();
+/// assert_eq!(graph.len(), 2);
+/// assert_eq!(chains.len(), 1);
+/// assert_eq!(chains[0].total_rebuilds(), 2);
+/// ```
+#[must_use]
+pub fn analyze_log_lines<I: IntoIterator<Item = String>>(lines: I) -> RebuildGraph {
+    let mut graph = RebuildGraph::new();
+    for line in lines {
+        for entry in parse_rebuild_entry(&line) {
+            graph.add_node(
+                RebuildNode::new(entry.package, entry.reason).with_timestamp(entry.timestamp),
+            );
+        }
+    }
+    graph.annotate_root_causes();
+    graph
+}
 
 #[derive(Debug)]
 pub enum AnalyzerError {
     CargoTomlNotFound(PathBuf),
+    /// The cargo binary could not be spawned because it does not exist
+    CargoNotFound(PathBuf),
     EmptyCommand,
+    /// A rebuild reason selected with `--fail-on` was found
+    RebuildTriggered(String),
+    /// A dirty line had a rebuild reason that could not be parsed
+    UnparsedReason {
+        line: String,
+    },
+    /// Cargo did not finish within the `--timeout`
+    Timeout(Duration),
+    /// `--profile` was given while the cargo arguments select a profile too
+    ConflictingProfile {
+        profile: String,
+        arg: String,
+    },
+    /// `--tui` was given without an interactive terminal to draw on
+    NotATerminal,
     Io(io::Error),
     Json(serde_json::Error),
 }
@@ -27,7 +88,26 @@ impl fmt::Display for AnalyzerError {
             Self::CargoTomlNotFound(path) => {
                 write!(f, "Cargo.toml not found at {}", path.display())
             }
+            Self::CargoNotFound(cargo_bin) => write!(
+                f,
+                "cargo not found at `{}`; make sure cargo is on PATH or point --cargo-bin at it",
+                cargo_bin.display()
+            ),
             Self::EmptyCommand => write!(f, "empty cargo command"),
+            Self::RebuildTriggered(reason) => write!(f, "rebuild triggered by {reason}"),
+            Self::UnparsedReason { line } => {
+                write!(f, "could not parse rebuild reason in: {}", line.trim())
+            }
+            Self::Timeout(timeout) => {
+                write!(f, "cargo did not finish within {}s", timeout.as_secs())
+            }
+            Self::ConflictingProfile { profile, arg } => {
+                write!(
+                    f,
+                    "--profile {profile} conflicts with `{arg}` in the cargo arguments"
+                )
+            }
+            Self::NotATerminal => write!(f, "--tui needs an interactive terminal"),
             Self::Io(e) => write!(f, "IO error: {e}"),
             Self::Json(e) => write!(f, "JSON error: {e}"),
         }
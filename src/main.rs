@@ -1,6 +1,6 @@
 use std::process::ExitCode;
 
-use cargo_frequent::Config;
+use cargo_frequent::{AnalyzerError, Config};
 
 fn main() -> ExitCode {
     let cli = Config::parse_args();
@@ -8,6 +8,10 @@ fn main() -> ExitCode {
 
     match cli.run() {
         Ok(()) => ExitCode::SUCCESS,
+        Err(e @ AnalyzerError::RebuildTriggered(_)) => {
+            eprintln!("Error: {e}");
+            ExitCode::from(2)
+        }
         Err(e) => {
             eprintln!("Error: {e}");
             ExitCode::FAILURE
@@ -0,0 +1,319 @@
+//! Snapshots of an analysis run that can be saved and compared
+//!
+//! Runs are compared by the reason keys used for deduplication, so two runs
+//! on different machines or checkouts can still be matched up.
+
+use std::{
+    collections::HashSet,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    rebuild_graph::{RebuildGraph, RebuildNode, RootCauseChain},
+    rebuild_reason::ReasonKind,
+    rebuild_reporter::{JsonStyle, RebuildTree, cascade_tree},
+};
+
+/// Version of the `--json` output layout, raised on incompatible changes
+///
+/// Version 2 added the nested `cascades` to each root cause.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Envelope around the `--json` root causes so consumers can detect format
+/// changes
+#[derive(Serialize)]
+struct VersionedOutput<'a> {
+    schema_version: u32,
+    generated_by: &'static str,
+    root_causes: Vec<VersionedRootCause<'a>>,
+}
+
+#[derive(Serialize)]
+struct VersionedRootCause<'a> {
+    #[serde(flatten)]
+    chain: &'a RootCauseChain,
+    /// The affected packages nested under the unit whose change rebuilt them
+    cascades: Vec<RebuildTree>,
+}
+
+/// Outcome of one analysis run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RebuildAnalysis {
+    /// Number of unique rebuilt units
+    #[serde(alias = "total_rebuilds")]
+    pub unique_rebuilds: usize,
+    /// Number of rebuild triggers in the log, duplicates included
+    #[serde(default)]
+    pub total_observed: usize,
+    /// Rebuilt units per reason key, most frequent first
+    pub reasons: Vec<(String, usize)>,
+    /// Every unique rebuilt unit in the order cargo reported it
+    #[serde(default)]
+    pub nodes: Vec<RebuildNode>,
+}
+
+impl RebuildAnalysis {
+    #[must_use]
+    pub fn from_graph(graph: &RebuildGraph) -> Self {
+        Self {
+            unique_rebuilds: graph.len(),
+            total_observed: graph.len() + graph.duplicate_count(),
+            reasons: graph.reason_frequencies(|_| true),
+            nodes: graph.nodes().to_vec(),
+        }
+    }
+
+    /// Serialize root cause chains of `graph` inside the versioned `--json`
+    /// envelope
+    ///
+    /// # Errors
+    /// Returns error if serialization fails
+    pub fn to_versioned_json(
+        chains: &[RootCauseChain],
+        graph: &RebuildGraph,
+        style: JsonStyle,
+    ) -> Result<String, serde_json::Error> {
+        let root_causes = chains
+            .iter()
+            .map(|chain| {
+                // Only nest the packages the chain still lists after filtering
+                let listed: HashSet<_> = chain
+                    .affected_packages
+                    .iter()
+                    .map(|node| &node.package)
+                    .collect();
+                let cascades = cascade_tree(graph, chain).map_or_else(Vec::new, |mut tree| {
+                    tree.retain(&|node| listed.contains(&node.package));
+                    tree.cascades
+                });
+                VersionedRootCause { chain, cascades }
+            })
+            .collect();
+        style.serialize(&VersionedOutput {
+            schema_version: SCHEMA_VERSION,
+            generated_by: concat!(env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION")),
+            root_causes,
+        })
+    }
+
+    /// Compare this run against an earlier `baseline` run
+    #[must_use]
+    pub fn diff(&self, baseline: &Self) -> AnalysisDiff {
+        let missing_from = |analysis: &Self, reasons: &[(String, usize)]| -> Vec<String> {
+            reasons
+                .iter()
+                .filter(|(key, _)| !analysis.reasons.iter().any(|(other, _)| other == key))
+                .map(|(key, _)| key.clone())
+                .collect()
+        };
+
+        AnalysisDiff {
+            added: missing_from(baseline, &self.reasons),
+            removed: missing_from(self, &baseline.reasons),
+            baseline_rebuilds: baseline.unique_rebuilds,
+            current_rebuilds: self.unique_rebuilds,
+        }
+    }
+}
+
+/// Number of rebuilt units per group of reason kinds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct RebuildSummary {
+    pub env_vars: usize,
+    pub dependencies: usize,
+    /// Rustflags, features, profile and target configuration changes
+    pub target_configs: usize,
+    /// Source, generated and missing files
+    pub files: usize,
+    pub other: usize,
+    pub total: usize,
+}
+
+impl RebuildSummary {
+    #[must_use]
+    pub fn from_nodes<'a>(nodes: impl IntoIterator<Item = &'a RebuildNode>) -> Self {
+        let mut summary = Self::default();
+        for node in nodes {
+            let count = match node.reason.kind() {
+                ReasonKind::EnvVar => &mut summary.env_vars,
+                ReasonKind::Dependency => &mut summary.dependencies,
+                ReasonKind::Rustflags
+                | ReasonKind::Features
+                | ReasonKind::Profile
+                | ReasonKind::TargetConfig => &mut summary.target_configs,
+                ReasonKind::SourceFile | ReasonKind::GeneratedFile | ReasonKind::MissingFile => {
+                    &mut summary.files
+                }
+                ReasonKind::Unknown => &mut summary.other,
+            };
+            *count += 1;
+            summary.total += 1;
+        }
+        summary
+    }
+}
+
+impl Display for RebuildSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        writeln!(f, "env_vars: {}", self.env_vars)?;
+        writeln!(f, "dependencies: {}", self.dependencies)?;
+        writeln!(f, "target_configs: {}", self.target_configs)?;
+        writeln!(f, "files: {}", self.files)?;
+        writeln!(f, "other: {}", self.other)?;
+        writeln!(f, "total: {}", self.total)
+    }
+}
+
+/// Reasons that appeared or disappeared between two runs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnalysisDiff {
+    /// Reason keys only present in the current run
+    pub added: Vec<String>,
+    /// Reason keys only present in the baseline
+    pub removed: Vec<String>,
+    pub baseline_rebuilds: usize,
+    pub current_rebuilds: usize,
+}
+
+impl Display for AnalysisDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        writeln!(f, "Compared with baseline:")?;
+        for reason in &self.added {
+            writeln!(f, "  + {reason}")?;
+        }
+        for reason in &self.removed {
+            writeln!(f, "  - {reason}")?;
+        }
+        if self.added.is_empty() && self.removed.is_empty() {
+            writeln!(f, "  Same rebuild reasons")?;
+        }
+
+        let (sign, change) = if self.current_rebuilds >= self.baseline_rebuilds {
+            ('+', self.current_rebuilds - self.baseline_rebuilds)
+        } else {
+            ('-', self.baseline_rebuilds - self.current_rebuilds)
+        };
+        writeln!(
+            f,
+            "  Rebuilt units: {} -> {} ({sign}{change})",
+            self.baseline_rebuilds, self.current_rebuilds
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        rebuild_graph::{PackageTarget, tests::cc_cascade_graph},
+        rebuild_reason::RebuildReason,
+        rebuild_reporter::to_json,
+    };
+
+    #[test]
+    fn diff_lists_added_and_removed_reasons() {
+        let baseline: RebuildAnalysis = serde_json::from_str(
+            r#"{"total_rebuilds": 5, "reasons": [["env:CC (unset -> 'gcc')", 3], ["profile changed", 2]]}"#,
+        )
+        .unwrap();
+        let current: RebuildAnalysis = serde_json::from_str(
+            r#"{"total_rebuilds": 2, "reasons": [["profile changed", 1], ["file:src/lib.rs", 1]]}"#,
+        )
+        .unwrap();
+
+        let diff = current.diff(&baseline);
+
+        assert_eq!(diff.added, ["file:src/lib.rs"]);
+        assert_eq!(diff.removed, ["env:CC (unset -> 'gcc')"]);
+        let rendered = diff.to_string();
+        assert!(
+            rendered.contains("Rebuilt units: 5 -> 2 (-3)"),
+            "Net change missing: {rendered}"
+        );
+    }
+
+    #[test]
+    fn snapshot_round_trips_through_json() {
+        let graph = cc_cascade_graph();
+        let analysis = RebuildAnalysis::from_graph(&graph);
+
+        let json = serde_json::to_string_pretty(&analysis).unwrap();
+        let restored: RebuildAnalysis = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored.nodes.len(), graph.len());
+        assert_eq!(restored, analysis);
+    }
+
+    #[test]
+    fn summary_counts_units_per_kind_group() {
+        let nodes = [
+            RebuildReason::ProfileConfigurationChanged,
+            RebuildReason::TargetConfigurationChanged,
+            RebuildReason::MissingFile {
+                path: "build/out.rs".to_string(),
+            },
+            RebuildReason::Unknown("NothingObvious".to_string()),
+        ]
+        .map(|reason| RebuildNode::new(PackageTarget::new("app v0.1.0", None), reason));
+
+        let summary = RebuildSummary::from_nodes(&nodes);
+
+        assert_eq!(
+            summary,
+            RebuildSummary {
+                target_configs: 2,
+                files: 1,
+                other: 1,
+                total: 4,
+                ..RebuildSummary::default()
+            }
+        );
+    }
+
+    #[test]
+    fn versioned_json_nests_cascades_in_flat_root_causes() {
+        let mut graph = RebuildGraph::new();
+        graph.add_node(RebuildNode::new(
+            PackageTarget::new("a v0.1.0", None),
+            RebuildReason::ProfileConfigurationChanged,
+        ));
+        for (package, dependency) in [("b v0.1.0", "a"), ("c v0.1.0", "b")] {
+            graph.add_node(RebuildNode::new(
+                PackageTarget::new(package, None),
+                RebuildReason::UnitDependencyInfoChanged {
+                    name: dependency.to_string(),
+                    old_fingerprint: "1".to_string(),
+                    new_fingerprint: "2".to_string(),
+                    context: None,
+                },
+            ));
+        }
+        let chains = graph.root_cause_chains();
+
+        let mut versioned: serde_json::Value = serde_json::from_str(
+            &RebuildAnalysis::to_versioned_json(&chains, &graph, JsonStyle::Pretty).unwrap(),
+        )
+        .unwrap();
+        let flat: serde_json::Value =
+            serde_json::from_str(&to_json(&chains, JsonStyle::Pretty).unwrap()).unwrap();
+
+        assert_eq!(versioned["schema_version"], SCHEMA_VERSION);
+        assert!(
+            versioned["generated_by"]
+                .as_str()
+                .is_some_and(|name| name.starts_with("cargo-frequent ")),
+            "Unexpected generator: {versioned}"
+        );
+        let cascades = versioned["root_causes"][0]
+            .as_object_mut()
+            .and_then(|root_cause| root_cause.remove("cascades"))
+            .unwrap();
+        assert_eq!(
+            cascades[0]["cascades"][0]["node"]["package"]["package_id"],
+            "c v0.1.0"
+        );
+        assert_eq!(versioned["root_causes"], flat);
+    }
+}
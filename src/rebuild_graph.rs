@@ -1,22 +1,25 @@
 //! Rebuild causality graph for tracking root causes of rebuilds
 //!
-//! Cargo's rebuild triggers form a directed acyclic graph where:
+//! Cargo's rebuild triggers form a directed, normally acyclic graph where:
 //! - Root causes are nodes with no incoming edges (file changes, env var
 //!   changes)
 //! - `UnitDependencyInfoChanged` creates edges between dependent packages
 //! - Finding root causes means traversing back to nodes with in-degree 0
 
 use std::{
-    collections::{HashMap, HashSet},
+    cmp::Reverse,
+    collections::{BTreeSet, HashMap, HashSet, VecDeque},
     fmt::{Display, Formatter, Result as FmtResult},
+    iter,
+    time::Duration,
 };
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-use crate::rebuild_reason::RebuildReason;
+use crate::rebuild_reason::{DependencyChangeContext, RebuildReason};
 
 /// Identifies a compilation unit in the rebuild graph
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct PackageTarget {
     pub package_id: String,
     pub target: Option<String>,
@@ -29,15 +32,138 @@ impl PackageTarget {
             target,
         }
     }
+
+    /// Crate name without version or source, e.g. `libz-sys` for
+    /// "libz-sys v1.1.23"
+    #[must_use]
+    pub fn name(&self) -> &str {
+        self.package_id
+            .split_whitespace()
+            .next()
+            .unwrap_or(&self.package_id)
+    }
+
+    /// Version component of the package id, e.g. `v1.1.23` for
+    /// "libz-sys v1.1.23"
+    #[must_use]
+    pub fn version(&self) -> Option<&str> {
+        self.package_id
+            .split_whitespace()
+            .nth(1)
+            .filter(|version| version.starts_with('v'))
+    }
+
+    /// Returns true if the crate name starts with `prefix`, treating hyphens
+    /// and underscores as equivalent
+    #[must_use]
+    pub fn name_starts_with(&self, prefix: &str) -> bool {
+        normalize_crate_name(&extract_package_name(&self.package_id))
+            .starts_with(&normalize_crate_name(prefix))
+    }
+
+    /// Returns true if the package matches a `--package` style filter: a
+    /// crate name prefix, or an exact `name@version` with or without the
+    /// leading `v`
+    #[must_use]
+    pub fn matches_filter(&self, filter: &str) -> bool {
+        filter.split_once('@').map_or_else(
+            || self.name_starts_with(filter),
+            |(name, version)| {
+                self.is_package(name)
+                    && self.version().is_some_and(|own| {
+                        own.trim_start_matches('v') == version.trim_start_matches('v')
+                    })
+            },
+        )
+    }
+
+    /// Returns true if the crate name is `name`, treating hyphens and
+    /// underscores as equivalent
+    #[must_use]
+    pub fn is_package(&self, name: &str) -> bool {
+        normalize_crate_name(&extract_package_name(&self.package_id)) == normalize_crate_name(name)
+    }
+
+    /// Kind of the target, as far as its name tells. Cargo logs only the
+    /// target name, so this recognizes build scripts; other targets are
+    /// [`TargetKind::Unknown`] unless looked up in [`TargetKinds`].
+    #[must_use]
+    pub fn kind(&self) -> TargetKind {
+        match self.target.as_deref() {
+            Some(target) if target.starts_with("build-script-") => TargetKind::BuildScript,
+            _ => TargetKind::Unknown,
+        }
+    }
+}
+
+/// What kind of compilation unit a target is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetKind {
+    Lib,
+    Bin,
+    Test,
+    Bench,
+    Example,
+    BuildScript,
+    /// Rebuilding a proc-macro invalidates every crate that uses it
+    ProcMacro,
+    Unknown,
+}
+
+impl TargetKind {
+    /// Kind of a target from the `kind` list `cargo metadata` reports for it,
+    /// e.g. `["proc-macro"]` or `["custom-build"]`
+    #[must_use]
+    pub fn from_cargo_kinds<'a>(kinds: impl IntoIterator<Item = &'a str>) -> Self {
+        kinds
+            .into_iter()
+            .map(|kind| match kind {
+                "proc-macro" => Self::ProcMacro,
+                "lib" | "rlib" | "dylib" | "cdylib" | "staticlib" => Self::Lib,
+                "bin" => Self::Bin,
+                "test" => Self::Test,
+                "bench" => Self::Bench,
+                "example" => Self::Example,
+                "custom-build" => Self::BuildScript,
+                _ => Self::Unknown,
+            })
+            .find(|&kind| kind != Self::Unknown)
+            .unwrap_or(Self::Unknown)
+    }
+}
+
+/// Kinds of the targets of a project and its dependencies, keyed by package
+/// and target name as cargo logs them
+#[derive(Debug, Clone, Default)]
+pub struct TargetKinds {
+    kinds: HashMap<(String, String), TargetKind>,
+}
+
+impl TargetKinds {
+    pub fn insert(&mut self, package: &str, target: &str, kind: TargetKind) {
+        self.kinds
+            .insert((normalize_crate_name(package), target.to_string()), kind);
+    }
+
+    /// Kind of the target `package` was built for, falling back to what the
+    /// target name tells for targets that are not known
+    #[must_use]
+    pub fn kind(&self, package: &PackageTarget) -> TargetKind {
+        package
+            .target
+            .as_ref()
+            .and_then(|target| {
+                self.kinds
+                    .get(&(normalize_crate_name(package.name()), target.clone()))
+            })
+            .copied()
+            .unwrap_or_else(|| package.kind())
+    }
 }
 
 impl Display for PackageTarget {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-        let package_name = self
-            .package_id
-            .split_whitespace()
-            .next()
-            .unwrap_or(&self.package_id);
+        let package_name = self.name();
 
         match &self.target {
             Some(target) => write!(f, "{package_name} [{target}]"),
@@ -47,16 +173,29 @@ impl Display for PackageTarget {
 }
 
 /// A node in the rebuild graph: a package with its direct rebuild reason
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RebuildNode {
     pub package: PackageTarget,
     pub reason: RebuildReason,
+    /// Time since the start of the cargo run at which the trigger was logged
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<Duration>,
 }
 
 impl RebuildNode {
     #[must_use]
     pub const fn new(package: PackageTarget, reason: RebuildReason) -> Self {
-        Self { package, reason }
+        Self {
+            package,
+            reason,
+            timestamp: None,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_timestamp(mut self, timestamp: Option<Duration>) -> Self {
+        self.timestamp = timestamp;
+        self
     }
 
     /// Returns true if this is a root cause (not caused by another package
@@ -78,10 +217,23 @@ pub struct RebuildGraph {
     nodes: Vec<RebuildNode>,
     /// Map from dependency name to indices of nodes that caused its rebuild
     dependency_causes: HashMap<String, Vec<usize>>,
+    /// Map from normalized dependency name to indices of the nodes rebuilt
+    /// because it changed
+    dependents_by_name: HashMap<String, Vec<usize>>,
+    /// Map from normalized package name to indices of its nodes
+    nodes_by_name: HashMap<String, Vec<usize>>,
+    /// Indices of the nodes each node's change directly rebuilt, resolved as
+    /// nodes are inserted so either end of an edge may arrive first
+    edges: Vec<Vec<usize>>,
     /// Map from package to its node index
     package_to_node: HashMap<PackageTarget, usize>,
-    /// Track seen (`package_name`, `reason_key`) to deduplicate
-    seen_entries: HashSet<(String, String)>,
+    /// Node index of each seen (`package_name`, `target`, `reason_key`), to
+    /// deduplicate
+    seen_entries: HashMap<EntryKey, usize>,
+    /// Number of analyzed logs in which each node was rebuilt
+    occurrences: Vec<usize>,
+    /// Number of nodes dropped as duplicates of an earlier node
+    duplicates: usize,
 }
 
 impl RebuildGraph {
@@ -90,19 +242,63 @@ impl RebuildGraph {
         Self::default()
     }
 
-    /// Add a rebuild node to the graph, deduplicating by package name and
-    /// reason
+    /// Add a rebuild node to the graph, deduplicating by package name, target
+    /// and reason
     pub fn add_node(&mut self, node: RebuildNode) -> Option<usize> {
-        let package_name = extract_package_name(&node.package.package_id);
-        let reason_key = node.reason.to_string();
-        let entry_key = (package_name.clone(), reason_key);
-
-        if !self.seen_entries.insert(entry_key) {
+        let key = entry_key(&node);
+        if self.seen_entries.contains_key(&key) {
+            self.duplicates += 1;
             return None;
         }
+        Some(self.insert_node(key, node, 1))
+    }
+
+    /// Union `other` into this graph, e.g. the graph of another cargo log.
+    /// Units rebuilt in both keep this graph's node and sum their occurrences.
+    pub fn merge(&mut self, other: Self) {
+        self.duplicates += other.duplicates;
+        for (node, occurrences) in other.nodes.into_iter().zip(other.occurrences) {
+            let key = entry_key(&node);
+            if let Some(&idx) = self.seen_entries.get(&key) {
+                self.occurrences[idx] += occurrences;
+            } else {
+                self.insert_node(key, node, occurrences);
+            }
+        }
+    }
 
+    /// The nodes of this graph that `other` has too, keyed like `add_node`
+    /// deduplicates, e.g. the triggers that persist across two cargo runs
+    #[must_use]
+    pub fn intersection(&self, other: &Self) -> Self {
+        self.retain_keys(|key| other.seen_entries.contains_key(key))
+    }
+
+    /// The nodes of this graph that `other` lacks, keyed like `add_node`
+    /// deduplicates, e.g. the triggers a later cargo run no longer had
+    #[must_use]
+    pub fn difference(&self, other: &Self) -> Self {
+        self.retain_keys(|key| !other.seen_entries.contains_key(key))
+    }
+
+    fn retain_keys(&self, keep: impl Fn(&EntryKey) -> bool) -> Self {
+        let mut retained = Self::new();
+        for (node, &occurrences) in self.nodes.iter().zip(&self.occurrences) {
+            let key = entry_key(node);
+            if keep(&key) {
+                retained.insert_node(key, node.clone(), occurrences);
+            }
+        }
+        retained
+    }
+
+    fn insert_node(&mut self, key: EntryKey, node: RebuildNode, occurrences: usize) -> usize {
+        let package_name = key.0.clone();
         let idx = self.nodes.len();
+        self.seen_entries.insert(key, idx);
+        self.occurrences.push(occurrences);
         self.package_to_node.insert(node.package.clone(), idx);
+        self.link(idx, &node);
 
         // If this is a root cause, record it as a potential cause for dependencies
         if node.is_root_cause() {
@@ -111,105 +307,313 @@ impl RebuildGraph {
                 .or_default()
                 .push(idx);
         }
+        if let RebuildReason::UnitDependencyInfoChanged { name, .. } = &node.reason {
+            self.dependents_by_name
+                .entry(normalize_crate_name(name))
+                .or_default()
+                .push(idx);
+        }
 
         self.nodes.push(node);
-        Some(idx)
+        idx
+    }
+
+    // Adds the edges between the new node `idx` and the nodes already present
+    fn link(&mut self, idx: usize, node: &RebuildNode) {
+        let name = normalize_crate_name(&extract_package_name(&node.package.package_id));
+        let dependents = self
+            .dependents_by_name
+            .get(&name)
+            .into_iter()
+            .flatten()
+            .copied()
+            .collect();
+        if let RebuildReason::UnitDependencyInfoChanged { name: changed, .. } = &node.reason {
+            let changed = normalize_crate_name(changed);
+            for &cause in self.nodes_by_name.get(&changed).into_iter().flatten() {
+                self.edges[cause].push(idx);
+            }
+        }
+        self.edges.push(dependents);
+        self.nodes_by_name.entry(name).or_default().push(idx);
+    }
+
+    /// Number of unique nodes
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Number of nodes dropped by `add_node` as duplicates
+    #[must_use]
+    pub const fn duplicate_count(&self) -> usize {
+        self.duplicates
+    }
+
+    /// All unique nodes in insertion order
+    #[must_use]
+    pub fn nodes(&self) -> &[RebuildNode] {
+        &self.nodes
     }
 
     /// Find all root causes (nodes that are not caused by dependency changes)
+    #[cfg(test)]
     #[must_use]
     pub fn root_causes(&self) -> Vec<&RebuildNode> {
         self.nodes.iter().filter(|n| n.is_root_cause()).collect()
     }
 
-    /// Find root causes with their full downstream impact chains
+    /// Find root causes with their full downstream impact chains, ordered by
+    /// reason and then package so the same log always reports the same way
     #[must_use]
     pub fn root_cause_chains(&self) -> Vec<RootCauseChain> {
-        let mut chains = Vec::new();
-        let root_causes: Vec<_> = self
+        let mut memo = HashMap::new();
+        let mut chains: Vec<_> = self
             .nodes
             .iter()
             .enumerate()
             .filter(|(_, n)| n.is_root_cause())
+            .map(|(root_idx, root_node)| RootCauseChain {
+                root_cause: root_node.clone(),
+                affected_packages: self
+                    .find_affected_indices(root_idx, &mut memo)
+                    .into_iter()
+                    .map(|idx| self.nodes[idx].clone())
+                    .collect(),
+            })
             .collect();
+        chains.sort_by(|a, b| {
+            (&a.root_cause.reason, &a.root_cause.package)
+                .cmp(&(&b.root_cause.reason, &b.root_cause.package))
+        });
+        chains
+    }
 
-        for (root_idx, root_node) in root_causes {
-            let affected = self.find_affected_packages(root_idx);
-            chains.push(RootCauseChain {
-                root_cause: root_node.clone(),
-                affected_packages: affected,
+    /// Root cause chains ordered by how many rebuilds they caused, largest
+    /// first. Chains with equal impact keep the canonical order of
+    /// [`Self::root_cause_chains`], by reason and then package.
+    #[must_use]
+    pub fn root_causes_by_impact(&self) -> Vec<RootCauseChain> {
+        let mut chains = self.root_cause_chains();
+        chains.sort_by_key(|chain| Reverse(chain.total_rebuilds()));
+        chains
+    }
+
+    /// Whether the graph looks like a build from a clean target directory:
+    /// many units, nearly all rebuilt on their own for a changed or missing
+    /// file. Such a graph says nothing about avoidable rebuilds.
+    #[must_use]
+    pub fn looks_like_first_build(&self) -> bool {
+        const MIN_UNITS: usize = 10;
+
+        let total = self.nodes.len();
+        let roots = self.nodes.iter().filter(|n| n.is_root_cause()).count();
+        let fresh = self
+            .nodes
+            .iter()
+            .filter(|n| {
+                matches!(
+                    n.reason,
+                    RebuildReason::FileChanged { .. } | RebuildReason::MissingFile { .. }
+                )
+            })
+            .count();
+        total >= MIN_UNITS && roots * 10 >= total * 9 && fresh * 10 >= total * 9
+    }
+
+    /// Earliest and latest timestamp of the nodes, if any node has one
+    #[must_use]
+    pub fn trigger_timespan(&self) -> Option<(Duration, Duration)> {
+        let timestamps = self.nodes.iter().filter_map(|node| node.timestamp);
+        Some((timestamps.clone().min()?, timestamps.max()?))
+    }
+
+    /// Number of rebuilt units that pass `keep` per distinct reason, summed
+    /// over merged logs, most frequent first. A root cause is credited with
+    /// the units it cascaded into as well as its own. Reasons with equal
+    /// counts keep their insertion order.
+    #[must_use]
+    pub fn reason_frequencies(&self, keep: impl Fn(&RebuildNode) -> bool) -> Vec<(String, usize)> {
+        self.reason_groups(keep)
+            .into_iter()
+            .map(|(reason, count)| (reason_dedup_key(reason), count))
+            .collect()
+    }
+
+    /// The first reason of each group in [`Self::reason_frequencies`] with
+    /// the number of units rebuilt for it
+    #[must_use]
+    pub fn reason_groups(
+        &self,
+        keep: impl Fn(&RebuildNode) -> bool,
+    ) -> Vec<(&RebuildReason, usize)> {
+        let mut memo = HashMap::new();
+        let cascades: HashMap<usize, Vec<usize>> = (0..self.nodes.len())
+            .filter(|&idx| self.nodes[idx].is_root_cause())
+            .map(|idx| (idx, self.find_affected_indices(idx, &mut memo)))
+            .collect();
+        let reached: HashSet<usize> = cascades.values().flatten().copied().collect();
+
+        let weight = |idx: usize| {
+            if keep(&self.nodes[idx]) {
+                self.occurrences[idx]
+            } else {
+                0
+            }
+        };
+        // Dependency changes without a root cause in the log count on their own
+        let weighted = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, node)| match cascades.get(&idx) {
+                Some(affected) => Some((
+                    node,
+                    weight(idx) + affected.iter().map(|&a| weight(a)).sum::<usize>(),
+                )),
+                None if reached.contains(&idx) => None,
+                None => Some((node, weight(idx))),
+            })
+            .filter(|&(_, units)| units > 0);
+        Self::frequencies(weighted, |node| reason_dedup_key(&node.reason))
+            .into_iter()
+            .map(|(node, count)| (&node.reason, count))
+            .collect()
+    }
+
+    /// Number of rebuilt units that pass `keep` per crate, summed over merged
+    /// logs, most frequent first
+    #[must_use]
+    pub fn package_frequencies(&self, keep: impl Fn(&RebuildNode) -> bool) -> Vec<(String, usize)> {
+        let weighted = self
+            .nodes
+            .iter()
+            .zip(self.occurrences.iter().copied())
+            .filter(|(node, _)| keep(node));
+        Self::frequencies(weighted, |node| node.package.name().to_string())
+            .into_iter()
+            .map(|(node, count)| (node.package.name().to_string(), count))
+            .collect()
+    }
+
+    // First node per key with the summed weights of its group
+    fn frequencies<'a>(
+        weighted: impl IntoIterator<Item = (&'a RebuildNode, usize)>,
+        key_of: impl Fn(&RebuildNode) -> String,
+    ) -> Vec<(&'a RebuildNode, usize)> {
+        let mut slots: HashMap<String, usize> = HashMap::new();
+        let mut frequencies: Vec<(&RebuildNode, usize)> = Vec::new();
+        for (node, weight) in weighted {
+            let slot = *slots.entry(key_of(node)).or_insert_with(|| {
+                frequencies.push((node, 0));
+                frequencies.len() - 1
             });
+            frequencies[slot].1 += weight;
         }
+        frequencies.sort_by_key(|(_, count)| Reverse(*count));
+        frequencies
+    }
 
-        chains
+    /// Indices of the nodes rebuilt directly because the package of node
+    /// `idx` changed
+    #[must_use]
+    pub fn dependents(&self, idx: usize) -> &[usize] {
+        &self.edges[idx]
     }
 
-    /// Find all packages affected by a root cause (BFS traversal)
-    fn find_affected_packages(&self, root_idx: usize) -> Vec<RebuildNode> {
-        let root_name = extract_package_name(&self.nodes[root_idx].package.package_id);
-        let root_name_normalized = normalize_crate_name(&root_name);
-        let mut affected = Vec::new();
-        let mut visited = HashSet::new();
-        visited.insert(root_idx);
+    /// Index of the node `add_node` keeps for `node`, if any
+    #[must_use]
+    pub fn index_of(&self, node: &RebuildNode) -> Option<usize> {
+        self.seen_entries.get(&entry_key(node)).copied()
+    }
 
-        // Find nodes that were rebuilt because of this root cause
-        for (idx, node) in self.nodes.iter().enumerate() {
-            if visited.contains(&idx) {
+    /// Crates rebuilt by the root cause at `root_idx`, itself included, out of
+    /// `total_units` crates in the workspace
+    #[must_use]
+    pub fn impact_fraction(&self, root_idx: usize, total_units: usize) -> ImpactFraction {
+        let chain = RootCauseChain {
+            root_cause: self.nodes[root_idx].clone(),
+            affected_packages: self
+                .find_affected_indices(root_idx, &mut HashMap::new())
+                .into_iter()
+                .map(|idx| self.nodes[idx].clone())
+                .collect(),
+        };
+        chain.impact_fraction(total_units)
+    }
+
+    /// Record on every dependency change the root cause it traces back to.
+    /// Call once the graph is complete; the first root cause found wins.
+    pub fn annotate_root_causes(&mut self) {
+        let mut memo = HashMap::new();
+        for root_idx in 0..self.nodes.len() {
+            if !self.nodes[root_idx].is_root_cause() {
                 continue;
             }
+            let root = &self.nodes[root_idx];
+            let summary = format!("{} in {}", root.reason, root.package);
 
-            if let RebuildReason::UnitDependencyInfoChanged { name, .. } = &node.reason {
-                let dep_name_normalized = normalize_crate_name(name);
-                let is_affected = dep_name_normalized == root_name_normalized
-                    || self.is_transitively_affected(name, &root_name);
-
-                if is_affected {
-                    affected.push(node.clone());
-                    visited.insert(idx);
+            for idx in self.find_affected_indices(root_idx, &mut memo) {
+                if let RebuildReason::UnitDependencyInfoChanged { context, .. } =
+                    &mut self.nodes[idx].reason
+                {
+                    let context = context.get_or_insert_with(DependencyChangeContext::default);
+                    if context.root_cause.is_none() {
+                        context.root_cause = Some(summary.clone());
+                    }
                 }
             }
         }
-
-        affected
     }
 
-    /// Check if a dependency was transitively affected by a root cause
-    fn is_transitively_affected(&self, dep_name: &str, root_name: &str) -> bool {
-        let root_name_normalized = normalize_crate_name(root_name);
-        // Check if dep_name was rebuilt because of root_name through the chain
-        for node in &self.nodes {
-            let package_name = extract_package_name(&node.package.package_id);
-            let package_name_normalized = normalize_crate_name(&package_name);
-            let dep_name_normalized = normalize_crate_name(dep_name);
+    /// Indices of all nodes affected by a root cause, in insertion order.
+    /// Root causes in the same package share their result through `memo`.
+    fn find_affected_indices(
+        &self,
+        root_idx: usize,
+        memo: &mut HashMap<String, Vec<usize>>,
+    ) -> Vec<usize> {
+        let root_name = normalize_crate_name(&extract_package_name(
+            &self.nodes[root_idx].package.package_id,
+        ));
+        memo.entry(root_name)
+            .or_insert_with_key(|root_name| self.affected_by(root_name))
+            .clone()
+    }
 
-            if package_name_normalized != dep_name_normalized {
-                continue;
-            }
+    /// Indices of the nodes rebuilt directly or transitively because package
+    /// `root_name` changed: a breadth-first search over `dependents_by_name`.
+    /// Every package name is expanded once, so cycles between units terminate.
+    fn affected_by(&self, root_name: &str) -> Vec<usize> {
+        let mut affected = BTreeSet::new();
+        let mut expanded = HashSet::from([root_name.to_string()]);
+        let mut queue = VecDeque::from([root_name.to_string()]);
 
-            if let RebuildReason::UnitDependencyInfoChanged { name, .. } = &node.reason {
-                let name_normalized = normalize_crate_name(name);
-                if name_normalized == root_name_normalized {
-                    return true;
+        while let Some(name) = queue.pop_front() {
+            for &idx in self.dependents_by_name.get(&name).into_iter().flatten() {
+                if !affected.insert(idx) {
+                    continue;
                 }
-                if self.is_transitively_affected(name, root_name) {
-                    return true;
+                let package = normalize_crate_name(&extract_package_name(
+                    &self.nodes[idx].package.package_id,
+                ));
+                if expanded.insert(package.clone()) {
+                    queue.push_back(package);
                 }
             }
         }
-        false
-    }
 
-    /// Serialize the graph to a JSON string
-    ///
-    /// # Errors
-    /// Returns error if serialization fails
-    pub fn to_json(&self) -> Result<String, serde_json::Error> {
-        serde_json::to_string_pretty(&self.root_cause_chains())
+        affected.into_iter().collect()
     }
 }
 
 /// A root cause and all packages affected by it
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RootCauseChain {
     pub root_cause: RebuildNode,
     pub affected_packages: Vec<RebuildNode>,
@@ -218,10 +622,91 @@ pub struct RootCauseChain {
 impl RootCauseChain {
     /// Total number of rebuilds caused (root + affected)
     #[must_use]
-    #[cfg(test)]
     pub const fn total_rebuilds(&self) -> usize {
         1 + self.affected_packages.len()
     }
+
+    /// Number of distinct crates among the root cause and the affected units
+    #[must_use]
+    pub fn rebuilt_crates(&self) -> usize {
+        iter::once(&self.root_cause)
+            .chain(&self.affected_packages)
+            .map(|node| node.package.name())
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    /// [`Self::rebuilt_crates`] out of `total_units` crates in the workspace
+    #[must_use]
+    pub fn impact_fraction(&self, total_units: usize) -> ImpactFraction {
+        ImpactFraction {
+            rebuilt: self.rebuilt_crates(),
+            total: total_units,
+        }
+    }
+
+    /// Whether the root cause or any affected unit belongs to package `name`
+    #[must_use]
+    pub fn involves(&self, name: &str) -> bool {
+        self.root_cause.package.is_package(name)
+            || self
+                .affected_packages
+                .iter()
+                .any(|node| node.package.is_package(name))
+    }
+}
+
+/// Share of the workspace's crates that a root cause rebuilt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImpactFraction {
+    pub rebuilt: usize,
+    pub total: usize,
+}
+
+impl ImpactFraction {
+    /// Rebuilt crates as a percentage of the total, rounded to the nearest
+    /// whole percent
+    #[must_use]
+    pub const fn percent(self) -> usize {
+        if self.total == 0 {
+            return 0;
+        }
+        (self.rebuilt * 100 + self.total / 2) / self.total
+    }
+}
+
+impl Display for ImpactFraction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "rebuilt {}/{} crates ({}%)",
+            self.rebuilt,
+            self.total,
+            self.percent()
+        )
+    }
+}
+
+/// Package name, target and reason key identifying a unique node
+type EntryKey = (String, Option<String>, String);
+
+fn entry_key(node: &RebuildNode) -> EntryKey {
+    (
+        extract_package_name(&node.package.package_id),
+        node.package.target.clone(),
+        reason_dedup_key(&node.reason),
+    )
+}
+
+/// Key identifying equivalent reasons. File changes are keyed by path alone,
+/// so the same edit seen through different fingerprint files counts once.
+fn reason_dedup_key(reason: &RebuildReason) -> String {
+    match reason {
+        RebuildReason::FileChanged { path, .. } | RebuildReason::MissingFile { path } => {
+            format!("{}:{path}", reason.kind())
+        }
+        _ => reason.to_string(),
+    }
 }
 
 /// Extract just the package name from a `package_id` like "libz-sys v1.1.23"
@@ -240,41 +725,51 @@ fn normalize_crate_name(name: &str) -> String {
 }
 
 #[cfg(test)]
-mod tests {
+pub mod tests {
     use std::{
         fs,
         path::Path,
         process::{Command, Stdio},
+        time::Instant,
     };
 
     use assert_cmd::prelude::*;
     use tempfile::TempDir;
 
     use super::*;
-    use crate::fingerprint_parser::parse_rebuild_entry;
+    use crate::{
+        fingerprint_parser::parse_rebuild_entry,
+        rebuild_reason::IconStyle,
+        rebuild_reporter::{JsonStyle, to_json},
+    };
 
-    #[test]
-    fn builds_and_analyzes_rebuild_graph() {
+    /// libz-sys's build script rerun for a changed `CC`, which rebuilt
+    /// rusqlite
+    pub fn cc_cascade_graph() -> RebuildGraph {
         let mut graph = RebuildGraph::new();
-
         graph.add_node(RebuildNode::new(
-            PackageTarget::new("libz-sys v1.1.23", None),
+            PackageTarget::new("libz-sys v1.1.23", Some("build-script-build".to_string())),
             RebuildReason::EnvVarChanged {
                 name: "CC".to_string(),
                 old_value: Some("gcc".to_string()),
                 new_value: None,
             },
         ));
-
         graph.add_node(RebuildNode::new(
             PackageTarget::new("rusqlite v0.31.0", None),
             RebuildReason::UnitDependencyInfoChanged {
                 name: "libz-sys".to_string(),
-                old_fingerprint: "123".to_string(),
-                new_fingerprint: "456".to_string(),
+                old_fingerprint: "1".to_string(),
+                new_fingerprint: "2".to_string(),
                 context: None,
             },
         ));
+        graph
+    }
+
+    #[test]
+    fn builds_and_analyzes_rebuild_graph() {
+        let graph = cc_cascade_graph();
 
         let roots = graph.root_causes();
         assert_eq!(roots.len(), 1);
@@ -288,6 +783,604 @@ mod tests {
         assert_eq!(chains[0].total_rebuilds(), 2);
     }
 
+    #[test]
+    fn annotates_dependency_changes_with_their_root_cause() {
+        let mut graph = RebuildGraph::new();
+        graph.add_node(RebuildNode::new(
+            PackageTarget::new("libz-sys v1.1.23", Some("build-script-build".to_string())),
+            RebuildReason::EnvVarChanged {
+                name: "CC".to_string(),
+                old_value: None,
+                new_value: Some("clang".to_string()),
+            },
+        ));
+        graph.add_node(RebuildNode::new(
+            PackageTarget::new("rusqlite v0.31.0", None),
+            RebuildReason::UnitDependencyInfoChanged {
+                name: "libz-sys".to_string(),
+                old_fingerprint: "1".to_string(),
+                new_fingerprint: "2".to_string(),
+                context: None,
+            },
+        ));
+
+        graph.annotate_root_causes();
+
+        let explanation = graph.nodes()[1].reason.explanation_with(IconStyle::Ascii);
+        assert!(
+            explanation
+                .contains("Root cause: env:CC (unset -> 'clang') in libz-sys [build-script-build]"),
+            "Missing root cause: {explanation}"
+        );
+    }
+
+    #[test]
+    fn splits_package_id_into_name_and_version() {
+        let cases = [
+            ("libz-sys v1.1.23", "libz-sys", Some("v1.1.23")),
+            ("app", "app", None),
+            (
+                "serde v1.0.0 (registry+https://github.com/rust-lang/crates.io-index)",
+                "serde",
+                Some("v1.0.0"),
+            ),
+        ];
+
+        for (package_id, name, version) in cases {
+            let package = PackageTarget::new(package_id, None);
+            assert_eq!(package.name(), name);
+            assert_eq!(package.version(), version);
+        }
+    }
+
+    #[test]
+    fn filters_match_name_prefix_or_exact_version() {
+        let package = PackageTarget::new("libz-sys v1.1.23", None);
+
+        assert!(package.matches_filter("libz"), "Prefixes match");
+        assert!(package.matches_filter("libz_sys@1.1.23"), "Versions match");
+        assert!(
+            package.matches_filter("libz-sys@v1.1.23"),
+            "A leading v is optional"
+        );
+        assert!(
+            !package.matches_filter("libz-sys@1.1.22"),
+            "Other versions do not match"
+        );
+        assert!(
+            !package.matches_filter("libz@1.1.23"),
+            "Versioned names are exact"
+        );
+    }
+
+    #[test]
+    fn node_round_trips_through_json() {
+        let node = RebuildNode::new(
+            PackageTarget::new("openssl-sys v0.9.0", Some("build-script-build".to_string())),
+            RebuildReason::UnitDependencyInfoChanged {
+                name: "cc".to_string(),
+                old_fingerprint: "1".to_string(),
+                new_fingerprint: "2".to_string(),
+                context: Some(DependencyChangeContext {
+                    package_id: Some("cc v1.0.0".to_string()),
+                    target_type: None,
+                    root_cause: Some("env:CC".to_string()),
+                    fingerprint_file: None,
+                }),
+            },
+        );
+
+        let json = serde_json::to_string(&node).unwrap();
+        let restored: RebuildNode = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored, node);
+    }
+
+    #[test]
+    fn chain_involves_root_and_affected_packages() {
+        let chain = RootCauseChain {
+            root_cause: RebuildNode::new(
+                PackageTarget::new("lib-a v0.1.0", None),
+                RebuildReason::ProfileConfigurationChanged,
+            ),
+            affected_packages: vec![RebuildNode::new(
+                PackageTarget::new("lib_b v0.1.0", None),
+                RebuildReason::UnitDependencyInfoChanged {
+                    name: "lib_a".to_string(),
+                    old_fingerprint: "1".to_string(),
+                    new_fingerprint: "2".to_string(),
+                    context: None,
+                },
+            )],
+        };
+
+        assert!(chain.involves("lib_a"), "The root belongs to lib-a");
+        assert!(chain.involves("lib-b"), "lib-b was rebuilt");
+        assert!(!chain.involves("lib"), "Names must match exactly");
+    }
+
+    #[test]
+    fn orders_root_causes_by_impact() {
+        let mut graph = RebuildGraph::new();
+        graph.add_node(RebuildNode::new(
+            PackageTarget::new("app v0.1.0", None),
+            RebuildReason::FileChanged {
+                path: "src/main.rs".to_string(),
+                reference: None,
+            },
+        ));
+        graph.add_node(RebuildNode::new(
+            PackageTarget::new("libz-sys v1.1.23", None),
+            RebuildReason::EnvVarChanged {
+                name: "CC".to_string(),
+                old_value: None,
+                new_value: Some("clang".to_string()),
+            },
+        ));
+        graph.add_node(RebuildNode::new(
+            PackageTarget::new("rusqlite v0.31.0", None),
+            RebuildReason::UnitDependencyInfoChanged {
+                name: "libz-sys".to_string(),
+                old_fingerprint: "1".to_string(),
+                new_fingerprint: "2".to_string(),
+                context: None,
+            },
+        ));
+
+        let chains = graph.root_causes_by_impact();
+        let roots: Vec<_> = chains
+            .iter()
+            .map(|chain| chain.root_cause.package.package_id.as_str())
+            .collect();
+        assert_eq!(roots, ["libz-sys v1.1.23", "app v0.1.0"]);
+    }
+
+    #[test]
+    fn ranks_reasons_by_rebuilt_units() {
+        let mut graph = RebuildGraph::new();
+        let cc_change = RebuildReason::EnvVarChanged {
+            name: "CC".to_string(),
+            old_value: Some("gcc".to_string()),
+            new_value: Some("clang".to_string()),
+        };
+        graph.add_node(RebuildNode::new(
+            PackageTarget::new("app v0.1.0", None),
+            RebuildReason::ProfileConfigurationChanged,
+        ));
+        for package in ["libz-sys v1.1.23", "openssl-sys v0.9.0"] {
+            graph.add_node(RebuildNode::new(
+                PackageTarget::new(package, None),
+                cc_change.clone(),
+            ));
+        }
+
+        assert_eq!(
+            graph.reason_frequencies(|_| true),
+            [
+                ("env:CC ('gcc' -> 'clang')".to_string(), 2),
+                ("profile changed".to_string(), 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn credits_cascaded_rebuilds_to_their_root_reason() {
+        let mut graph = RebuildGraph::new();
+        graph.add_node(RebuildNode::new(
+            PackageTarget::new("libz-sys v1.1.23", None),
+            RebuildReason::EnvVarChanged {
+                name: "CC".to_string(),
+                old_value: None,
+                new_value: Some("clang".to_string()),
+            },
+        ));
+        for (package, dependency) in [("rusqlite v0.31.0", "libz-sys"), ("app v0.1.0", "rusqlite")]
+        {
+            graph.add_node(RebuildNode::new(
+                PackageTarget::new(package, None),
+                RebuildReason::UnitDependencyInfoChanged {
+                    name: dependency.to_string(),
+                    old_fingerprint: "1".to_string(),
+                    new_fingerprint: "2".to_string(),
+                    context: None,
+                },
+            ));
+        }
+        graph.add_node(RebuildNode::new(
+            PackageTarget::new("tool v0.1.0", None),
+            RebuildReason::ProfileConfigurationChanged,
+        ));
+
+        assert_eq!(
+            graph.reason_frequencies(|_| true),
+            [
+                ("env:CC (unset -> 'clang')".to_string(), 3),
+                ("profile changed".to_string(), 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn len_counts_only_added_nodes() {
+        let mut graph = RebuildGraph::new();
+        assert!(graph.is_empty(), "A new graph has no nodes");
+
+        let edit = RebuildNode::new(
+            PackageTarget::new("app v0.1.0", None),
+            RebuildReason::FileChanged {
+                path: "src/main.rs".to_string(),
+                reference: None,
+            },
+        );
+        let added = [
+            graph.add_node(edit.clone()),
+            graph.add_node(edit),
+            graph.add_node(RebuildNode::new(
+                PackageTarget::new("app v0.1.0", None),
+                RebuildReason::ProfileConfigurationChanged,
+            )),
+        ];
+
+        assert_eq!(added, [Some(0), None, Some(1)]);
+        assert_eq!(graph.len(), 2);
+        assert_eq!(graph.duplicate_count(), 1);
+        assert!(!graph.is_empty(), "Two nodes were added");
+    }
+
+    #[test]
+    fn counts_duplicate_nodes() {
+        let mut graph = RebuildGraph::new();
+        let node = RebuildNode::new(
+            PackageTarget::new("libz-sys v1.1.23", None),
+            RebuildReason::ProfileConfigurationChanged,
+        );
+
+        for _ in 0..3 {
+            graph.add_node(node.clone());
+        }
+
+        assert_eq!(graph.len(), 1);
+        assert_eq!(graph.duplicate_count(), 2);
+    }
+
+    #[test]
+    fn trigger_timespan_covers_first_and_last_trigger() {
+        let mut graph = RebuildGraph::new();
+        assert_eq!(graph.trigger_timespan(), None);
+
+        for (package, millis) in [("app v0.1.0", 2340), ("lib-a v0.1.0", 100)] {
+            graph.add_node(
+                RebuildNode::new(
+                    PackageTarget::new(package, None),
+                    RebuildReason::ProfileConfigurationChanged,
+                )
+                .with_timestamp(Some(Duration::from_millis(millis))),
+            );
+        }
+
+        assert_eq!(
+            graph.trigger_timespan(),
+            Some((Duration::from_millis(100), Duration::from_millis(2340)))
+        );
+    }
+
+    #[test]
+    fn links_dependents_whichever_node_comes_first() {
+        let dependency_change = |package: &str, name: &str| {
+            RebuildNode::new(
+                PackageTarget::new(package, None),
+                RebuildReason::UnitDependencyInfoChanged {
+                    name: name.to_string(),
+                    old_fingerprint: "1".to_string(),
+                    new_fingerprint: "2".to_string(),
+                    context: None,
+                },
+            )
+        };
+        let mut graph = RebuildGraph::new();
+        graph.add_node(dependency_change("app v0.1.0", "lib_b"));
+        graph.add_node(RebuildNode::new(
+            PackageTarget::new("lib-a v0.1.0", None),
+            RebuildReason::ProfileConfigurationChanged,
+        ));
+        graph.add_node(dependency_change("lib-b v0.1.0", "lib-a"));
+
+        assert_eq!(graph.dependents(1), [2]);
+        assert_eq!(graph.dependents(2), [0]);
+        assert!(graph.dependents(0).is_empty());
+    }
+
+    #[test]
+    fn merge_sums_occurrences_of_shared_units() {
+        let edit = |package: &str| {
+            RebuildNode::new(
+                PackageTarget::new(package, None),
+                RebuildReason::FileChanged {
+                    path: "src/lib.rs".to_string(),
+                    reference: None,
+                },
+            )
+        };
+        let mut monday = RebuildGraph::new();
+        monday.add_node(edit("lib-a v0.1.0"));
+        monday.add_node(edit("app v0.1.0"));
+        let mut tuesday = RebuildGraph::new();
+        tuesday.add_node(edit("lib-a v0.1.0"));
+
+        monday.merge(tuesday);
+
+        assert_eq!(monday.len(), 2);
+        assert_eq!(
+            monday.package_frequencies(|_| true),
+            [("lib-a".to_string(), 2), ("app".to_string(), 1)]
+        );
+        assert_eq!(
+            monday.reason_frequencies(|_| true),
+            [("file:src/lib.rs".to_string(), 3)]
+        );
+    }
+
+    #[test]
+    fn keeps_targets_of_one_package_apart() {
+        let mut graph = RebuildGraph::new();
+        let reason = RebuildReason::EnvVarChanged {
+            name: "RUSTC".to_string(),
+            old_value: None,
+            new_value: Some("rustc".to_string()),
+        };
+
+        for target in ["serde", "build-script-build", "serde"] {
+            graph.add_node(RebuildNode::new(
+                PackageTarget::new("serde v1.0.0", Some(target.to_string())),
+                reason.clone(),
+            ));
+        }
+
+        let targets: Vec<_> = graph
+            .nodes()
+            .iter()
+            .map(|node| node.package.target.as_deref())
+            .collect();
+        assert_eq!(targets, [Some("serde"), Some("build-script-build")]);
+    }
+
+    #[test]
+    fn dependency_cycle_terminates() {
+        let mut graph = RebuildGraph::new();
+        graph.add_node(RebuildNode::new(
+            PackageTarget::new("root v0.1.0", None),
+            RebuildReason::FileChanged {
+                path: "src/lib.rs".to_string(),
+                reference: None,
+            },
+        ));
+        for (package, dependency) in [("a v0.1.0", "b"), ("b v0.1.0", "a")] {
+            graph.add_node(RebuildNode::new(
+                PackageTarget::new(package, None),
+                RebuildReason::UnitDependencyInfoChanged {
+                    name: dependency.to_string(),
+                    old_fingerprint: "1".to_string(),
+                    new_fingerprint: "2".to_string(),
+                    context: None,
+                },
+            ));
+        }
+
+        let chains = graph.root_cause_chains();
+        assert_eq!(chains.len(), 1);
+        assert!(
+            chains[0].affected_packages.is_empty(),
+            "The cycle is not caused by the root: {chains:?}"
+        );
+    }
+
+    #[test]
+    fn all_fresh_graph_looks_like_a_first_build() {
+        let mut graph = RebuildGraph::new();
+        for idx in 0..20 {
+            graph.add_node(RebuildNode::new(
+                PackageTarget::new(format!("crate-{idx} v0.1.0"), None),
+                RebuildReason::MissingFile {
+                    path: format!("/target/debug/deps/libcrate_{idx}.rlib"),
+                },
+            ));
+        }
+        assert!(graph.looks_like_first_build());
+
+        let mut edit = RebuildGraph::new();
+        edit.add_node(RebuildNode::new(
+            PackageTarget::new("app v0.1.0", None),
+            RebuildReason::FileChanged {
+                path: "src/main.rs".to_string(),
+                reference: None,
+            },
+        ));
+        assert!(
+            !edit.looks_like_first_build(),
+            "Editing one file is an incremental build"
+        );
+    }
+
+    #[test]
+    fn intersection_and_difference_compare_nodes_of_two_graphs() {
+        let cc = RebuildNode::new(
+            PackageTarget::new("libz-sys v1.1.23", None),
+            RebuildReason::EnvVarChanged {
+                name: "CC".to_string(),
+                old_value: None,
+                new_value: Some("clang".to_string()),
+            },
+        );
+        let edit = RebuildNode::new(
+            PackageTarget::new("app v0.1.0", None),
+            RebuildReason::FileChanged {
+                path: "src/main.rs".to_string(),
+                reference: None,
+            },
+        );
+        let mut first = RebuildGraph::new();
+        first.add_node(edit.clone());
+        first.add_node(cc.clone());
+        let mut second = RebuildGraph::new();
+        second.add_node(cc.clone());
+
+        assert_eq!(first.intersection(&second).nodes(), [cc]);
+        assert!(first.intersection(&RebuildGraph::new()).is_empty());
+        assert_eq!(first.difference(&second).nodes(), [edit]);
+        assert!(second.difference(&first).is_empty());
+    }
+
+    #[test]
+    fn impact_fraction_counts_crates_against_the_total() {
+        let mut graph = RebuildGraph::new();
+        let root = graph
+            .add_node(RebuildNode::new(
+                PackageTarget::new("lib-a v0.1.0", Some("lib_a".to_string())),
+                RebuildReason::FileChanged {
+                    path: "src/lib.rs".to_string(),
+                    reference: None,
+                },
+            ))
+            .unwrap();
+        for (package, target) in [
+            ("lib-b v0.1.0", "lib_b"),
+            ("app v0.1.0", "app"),
+            ("app v0.1.0", "integration"),
+        ] {
+            graph.add_node(RebuildNode::new(
+                PackageTarget::new(package, Some(target.to_string())),
+                RebuildReason::UnitDependencyInfoChanged {
+                    name: "lib_a".to_string(),
+                    old_fingerprint: "1".to_string(),
+                    new_fingerprint: "2".to_string(),
+                    context: None,
+                },
+            ));
+        }
+
+        let fraction = graph.impact_fraction(root, 8);
+
+        assert_eq!(
+            fraction,
+            ImpactFraction {
+                rebuilt: 3,
+                total: 8
+            }
+        );
+        assert_eq!(fraction.percent(), 38);
+        assert_eq!(fraction.to_string(), "rebuilt 3/8 crates (38%)");
+    }
+
+    #[test]
+    fn chains_are_ordered_independent_of_insertion() {
+        let nodes = [
+            RebuildNode::new(
+                PackageTarget::new("app v0.1.0", None),
+                RebuildReason::TargetConfigurationChanged,
+            ),
+            RebuildNode::new(
+                PackageTarget::new("lib-b v0.1.0", None),
+                RebuildReason::ProfileConfigurationChanged,
+            ),
+            RebuildNode::new(
+                PackageTarget::new("libz-sys v1.1.23", None),
+                RebuildReason::EnvVarChanged {
+                    name: "CC".to_string(),
+                    old_value: None,
+                    new_value: Some("clang".to_string()),
+                },
+            ),
+            RebuildNode::new(
+                PackageTarget::new("lib-a v0.1.0", None),
+                RebuildReason::ProfileConfigurationChanged,
+            ),
+        ];
+        let chains_of = |nodes: &mut dyn Iterator<Item = &RebuildNode>| {
+            let mut graph = RebuildGraph::new();
+            for node in nodes {
+                graph.add_node(node.clone());
+            }
+            graph.root_cause_chains()
+        };
+
+        let forward = chains_of(&mut nodes.iter());
+        let backward = chains_of(&mut nodes.iter().rev());
+
+        assert_eq!(forward, backward);
+        let packages: Vec<_> = forward
+            .iter()
+            .map(|chain| chain.root_cause.package.name())
+            .collect();
+        assert_eq!(packages, ["libz-sys", "lib-a", "lib-b", "app"]);
+    }
+
+    #[test]
+    fn chains_of_hundreds_of_units_are_found_quickly() {
+        const ROOTS: usize = 10;
+        const DEPTH: usize = 40;
+
+        let mut graph = RebuildGraph::new();
+        for root in 0..ROOTS {
+            graph.add_node(RebuildNode::new(
+                PackageTarget::new(format!("crate-{root}-0 v0.1.0"), None),
+                RebuildReason::FileChanged {
+                    path: "src/lib.rs".to_string(),
+                    reference: None,
+                },
+            ));
+            for level in 1..DEPTH {
+                graph.add_node(RebuildNode::new(
+                    PackageTarget::new(format!("crate-{root}-{level} v0.1.0"), None),
+                    RebuildReason::UnitDependencyInfoChanged {
+                        name: format!("crate_{root}_{}", level - 1),
+                        old_fingerprint: "1".to_string(),
+                        new_fingerprint: "2".to_string(),
+                        context: None,
+                    },
+                ));
+            }
+        }
+        assert_eq!(graph.len(), ROOTS * DEPTH);
+
+        let start = Instant::now();
+        let chains = graph.root_cause_chains();
+        assert!(
+            start.elapsed() < Duration::from_secs(1),
+            "Took {:?}",
+            start.elapsed()
+        );
+
+        assert_eq!(chains.len(), ROOTS);
+        for (root, chain) in chains.iter().enumerate() {
+            assert_eq!(chain.total_rebuilds(), DEPTH);
+            assert!(
+                chain
+                    .affected_packages
+                    .iter()
+                    .all(|node| node.package.name().starts_with(&format!("crate-{root}-"))),
+                "Chain of root {root} crosses into another chain: {chain:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn features_change_from_log_is_a_root_cause() {
+        let log_line = r#"    0.051234567s  INFO prepare_target{force=false package_id=serde v1.0.0 target="serde"}: cargo::core::compiler::fingerprint:     dirty: FeaturesChanged { old: "default", new: "default, derive" }"#;
+
+        let graph = build_graph_from_logs(&[log_line.to_string()]);
+        let roots = graph.root_causes();
+
+        assert_eq!(roots.len(), 1);
+        assert_eq!(roots[0].package.package_id, "serde v1.0.0");
+        assert_eq!(
+            roots[0].reason,
+            RebuildReason::FeaturesChanged {
+                old: "default".to_string(),
+                new: "default, derive".to_string(),
+            }
+        );
+    }
+
     fn create_workspace_with_dependencies() -> TempDir {
         let temp_dir = TempDir::new().unwrap();
 
@@ -399,7 +1492,7 @@ fn main() {
     fn build_graph_from_logs(log_lines: &[String]) -> RebuildGraph {
         let mut graph = RebuildGraph::new();
         for line in log_lines {
-            if let Some(entry) = parse_rebuild_entry(line) {
+            for entry in parse_rebuild_entry(line) {
                 graph.add_node(RebuildNode::new(entry.package, entry.reason));
             }
         }
@@ -428,7 +1521,8 @@ pub fn greet() -> &'static str {
         let log_lines = collect_cargo_fingerprint_logs(workspace.path());
         let graph = build_graph_from_logs(&log_lines);
 
-        let json = graph.to_json().expect("JSON serialization should succeed");
+        let json = to_json(&graph.root_cause_chains(), JsonStyle::Pretty)
+            .expect("JSON serialization should succeed");
         let parsed: serde_json::Value =
             serde_json::from_str(&json).expect("JSON should be valid and parseable");
 
@@ -473,4 +1567,39 @@ pub fn greet() -> &'static str {
             "lib-a should be identified as a root cause since we modified it"
         );
     }
+
+    #[test]
+    fn classifies_targets_by_kind() {
+        let cargo_kind = |kinds: &[&str]| TargetKind::from_cargo_kinds(kinds.iter().copied());
+        assert_eq!(cargo_kind(&["proc-macro"]), TargetKind::ProcMacro);
+        assert_eq!(cargo_kind(&["rlib", "cdylib"]), TargetKind::Lib);
+        assert_eq!(cargo_kind(&["bin"]), TargetKind::Bin);
+        assert_eq!(cargo_kind(&["test"]), TargetKind::Test);
+        assert_eq!(cargo_kind(&["bench"]), TargetKind::Bench);
+        assert_eq!(cargo_kind(&["example"]), TargetKind::Example);
+        assert_eq!(cargo_kind(&["custom-build"]), TargetKind::BuildScript);
+        assert_eq!(cargo_kind(&[]), TargetKind::Unknown);
+
+        let mut kinds = TargetKinds::default();
+        kinds.insert("serde_derive", "serde_derive", TargetKind::ProcMacro);
+        kinds.insert("lib-a", "lib_a", TargetKind::Lib);
+        let kind = |package: &str, target: Option<&str>| {
+            kinds.kind(&PackageTarget::new(package, target.map(str::to_string)))
+        };
+
+        assert_eq!(
+            kind("serde_derive v1.0.200", Some("serde_derive")),
+            TargetKind::ProcMacro
+        );
+        assert_eq!(kind("lib-a v0.1.0", Some("lib_a")), TargetKind::Lib);
+        assert_eq!(
+            kind("libz-sys v1.1.23", Some("build-script-build")),
+            TargetKind::BuildScript
+        );
+        assert_eq!(
+            kind("rusqlite v0.31.0", Some("rusqlite")),
+            TargetKind::Unknown
+        );
+        assert_eq!(kind("rusqlite v0.31.0", None), TargetKind::Unknown);
+    }
 }
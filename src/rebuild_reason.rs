@@ -1,6 +1,11 @@
-use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::{
+    cmp::Ordering,
+    fmt::{Display, Formatter, Result as FmtResult},
+    path::{Component, Path},
+    str::FromStr,
+};
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 /// Rebuild reasons parsed from Cargo's fingerprint log output.
 ///
@@ -12,7 +17,7 @@ use serde::Serialize;
 ///
 /// Note: This is not using Cargo's internal types directly for stability
 /// reasons. The variants are based on the string format in Cargo's log output.
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[non_exhaustive]
 pub enum RebuildReason {
     EnvVarChanged {
@@ -38,15 +43,626 @@ pub enum RebuildReason {
     TargetConfigurationChanged,
     FileChanged {
         path: String,
+        /// Fingerprint file the changed path was compared against
+        reference: Option<String>,
+    },
+    /// A file the fingerprint depends on no longer exists
+    MissingFile {
+        path: String,
+    },
+    /// The path of the unit's dep-info file changed
+    DepInfoOutputChanged {
+        old: String,
+        new: String,
+    },
+    /// Precalculated fingerprint components, such as the metadata hash of a
+    /// path dependency, differ
+    PrecalculatedComponentsChanged {
+        old: String,
+        new: String,
+    },
+    /// The kind of local fingerprint stored for the unit changed, e.g. from
+    /// precalculated to mtime-based
+    LocalFingerprintTypeChanged {
+        old: String,
+        new: String,
+    },
+    /// The compiler changed, e.g. after a rustup toolchain switch. Cargo logs
+    /// `RustcChanged` without the versions, so they are usually unknown.
+    CompilerChanged {
+        old: Option<String>,
+        new: Option<String>,
     },
     Unknown(String),
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DependencyChangeContext {
     pub package_id: Option<String>,
     pub target_type: Option<String>,
     pub root_cause: Option<String>,
+    /// Fingerprint file of the rebuilt unit, logged at debug level
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint_file: Option<String>,
+}
+
+/// Coarse category of a rebuild reason, used for grouping and filtering.
+/// Kinds are ordered by declaration, which is the order reports list them in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReasonKind {
+    EnvVar,
+    Dependency,
+    Rustflags,
+    Features,
+    Profile,
+    TargetConfig,
+    SourceFile,
+    /// A file written by a build script into the target directory
+    GeneratedFile,
+    MissingFile,
+    Unknown,
+}
+
+impl Display for ReasonKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let label = match self {
+            Self::EnvVar => "env",
+            Self::Dependency => "dep",
+            Self::Rustflags => "rustflags",
+            Self::Features => "features",
+            Self::Profile => "profile",
+            Self::TargetConfig => "target",
+            Self::SourceFile => "file",
+            Self::GeneratedFile => "generated",
+            Self::MissingFile => "missing",
+            Self::Unknown => "unknown",
+        };
+        write!(f, "{label}")
+    }
+}
+
+/// How explanations mark their headline and sections
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IconStyle {
+    #[default]
+    Emoji,
+    /// Plain ASCII tags such as `[ENV]`, for terminals without emoji support
+    Ascii,
+}
+
+/// How much attention a rebuild reason deserves, from expected to suspicious
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Severity {
+    /// Expected after editing code, or a consequence of another rebuild
+    Info,
+    /// Avoidable churn in the build environment or configuration
+    Warn,
+    /// A reason that could not be recognized
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let label = match self {
+            Self::Info => "info",
+            Self::Warn => "warn",
+            Self::Error => "error",
+        };
+        write!(f, "{label}")
+    }
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    fn from_str(level: &str) -> Result<Self, Self::Err> {
+        match level {
+            "info" => Ok(Self::Info),
+            "warn" => Ok(Self::Warn),
+            "error" => Ok(Self::Error),
+            _ => Err(format!("expected info, warn or error, got `{level}`")),
+        }
+    }
+}
+
+impl ReasonKind {
+    const fn icon(self, style: IconStyle) -> &'static str {
+        match style {
+            IconStyle::Emoji => match self {
+                Self::EnvVar => "🔧",
+                Self::Dependency => "📦",
+                Self::Rustflags => "🚩",
+                Self::Features => "🧩",
+                Self::Profile => "📊",
+                Self::TargetConfig => "🎯",
+                Self::SourceFile => "📝",
+                Self::GeneratedFile => "🏗️",
+                Self::MissingFile => "🚫",
+                Self::Unknown => "❓",
+            },
+            IconStyle::Ascii => match self {
+                Self::EnvVar => "[ENV]",
+                Self::Dependency => "[DEP]",
+                Self::Rustflags => "[FLAGS]",
+                Self::Features => "[FEATURES]",
+                Self::Profile => "[PROFILE]",
+                Self::TargetConfig => "[TARGET]",
+                Self::SourceFile => "[FILE]",
+                Self::GeneratedFile => "[GENERATED]",
+                Self::MissingFile => "[MISSING]",
+                Self::Unknown => "[UNKNOWN]",
+            },
+        }
+    }
+}
+
+impl RebuildReason {
+    #[must_use]
+    pub fn kind(&self) -> ReasonKind {
+        match self {
+            Self::EnvVarChanged { .. } => ReasonKind::EnvVar,
+            Self::UnitDependencyInfoChanged { .. } => ReasonKind::Dependency,
+            Self::RustflagsChanged { .. } => ReasonKind::Rustflags,
+            Self::FeaturesChanged { .. } => ReasonKind::Features,
+            Self::ProfileConfigurationChanged => ReasonKind::Profile,
+            Self::TargetConfigurationChanged
+            | Self::DepInfoOutputChanged { .. }
+            | Self::PrecalculatedComponentsChanged { .. }
+            | Self::LocalFingerprintTypeChanged { .. }
+            | Self::CompilerChanged { .. } => ReasonKind::TargetConfig,
+            Self::FileChanged { path, reference }
+                if is_generated_source(path, reference.as_deref()) =>
+            {
+                ReasonKind::GeneratedFile
+            }
+            Self::FileChanged { .. } => ReasonKind::SourceFile,
+            Self::MissingFile { .. } => ReasonKind::MissingFile,
+            Self::Unknown(_) => ReasonKind::Unknown,
+        }
+    }
+
+    /// Unrecognized reasons are errors, avoidable ones warnings and source
+    /// edits or their consequences informational
+    #[must_use]
+    pub fn severity(&self) -> Severity {
+        if self.kind() == ReasonKind::Unknown {
+            Severity::Error
+        } else if self.is_avoidable() {
+            Severity::Warn
+        } else {
+            Severity::Info
+        }
+    }
+
+    /// Returns true if the rebuild was not caused by editing source code and
+    /// could be prevented by a more stable build environment
+    #[must_use]
+    pub fn is_avoidable(&self) -> bool {
+        matches!(
+            self.kind(),
+            ReasonKind::EnvVar
+                | ReasonKind::Rustflags
+                | ReasonKind::Features
+                | ReasonKind::Profile
+                | ReasonKind::TargetConfig
+                | ReasonKind::GeneratedFile
+        )
+    }
+
+    /// First line of the explanation, e.g. "Environment variable CC changed"
+    #[must_use]
+    pub fn title(&self) -> String {
+        self.explanation_parts().title
+    }
+
+    /// Multi-line human-readable explanation with actionable suggestions
+    #[must_use]
+    pub fn explanation(&self) -> String {
+        self.explanation_with(IconStyle::Emoji)
+    }
+
+    /// Like [`Self::explanation`], with the icons drawn in `icons` style
+    #[must_use]
+    pub fn explanation_with(&self, icons: IconStyle) -> String {
+        self.explanation_parts().build(icons)
+    }
+
+    /// Like [`Self::explanation_with`], without the suggestions: only the
+    /// title, the details and the root cause
+    #[must_use]
+    pub fn explanation_without_suggestions(&self, icons: IconStyle) -> String {
+        let mut parts = self.explanation_parts();
+        parts.suggestions.clear();
+        parts.build(icons)
+    }
+
+    fn explanation_parts(&self) -> ExplanationParts {
+        match self {
+            Self::EnvVarChanged {
+                name,
+                old_value,
+                new_value,
+            } => explain_env_var_change(name, old_value.as_deref(), new_value.as_deref()),
+            Self::UnitDependencyInfoChanged {
+                name,
+                old_fingerprint,
+                new_fingerprint,
+                context,
+            } => {
+                explain_dependency_change(name, old_fingerprint, new_fingerprint, context.as_ref())
+            }
+            Self::RustflagsChanged { old, new } => explain_rustflags_change(old, new),
+            Self::FeaturesChanged { old, new } => ExplanationParts::new(
+                ReasonKind::Features,
+                format!("Enabled features changed: {old} -> {new}"),
+            )
+            .suggestion(
+                "Different commands or workspace members may enable different features; build \
+                 with the same feature set each time",
+            ),
+            Self::ProfileConfigurationChanged => {
+                ExplanationParts::new(ReasonKind::Profile, "Profile configuration changed")
+                    .suggestion("A [profile] section or CARGO_PROFILE_* variable was modified")
+            }
+            Self::TargetConfigurationChanged => {
+                ExplanationParts::new(ReasonKind::TargetConfig, "Target configuration changed")
+                    .suggestion("The --target triple or target-specific configuration differs")
+            }
+            Self::FileChanged { path, reference } => {
+                explain_file_change(path, reference.as_deref())
+            }
+            Self::MissingFile { path } => {
+                ExplanationParts::new(ReasonKind::MissingFile, format!("File is missing: {path}"))
+                    .suggestion(
+                        "The file was deleted, or the target directory was partially cleaned",
+                    )
+                    .suggestion(
+                        "If the file is generated, check that the step producing it still runs",
+                    )
+            }
+            Self::DepInfoOutputChanged { old, new } => {
+                ExplanationParts::new(ReasonKind::TargetConfig, "Dep-info output path changed")
+                    .detail(format!("{old} -> {new}"))
+                    .suggestion(
+                        "The project directory was moved or CARGO_TARGET_DIR changed; build from \
+                         one location with one target directory",
+                    )
+            }
+            Self::PrecalculatedComponentsChanged { old, new } => ExplanationParts::new(
+                ReasonKind::TargetConfig,
+                "Precalculated fingerprint components changed",
+            )
+            .detail(format!("{old} -> {new}"))
+            .suggestion(
+                "The toolchain was updated or package metadata such as the version changed; pin \
+                 the toolchain with rust-toolchain.toml",
+            ),
+            Self::LocalFingerprintTypeChanged { old, new } => {
+                ExplanationParts::new(ReasonKind::TargetConfig, "Local fingerprint type changed")
+                    .detail(format!("{old} -> {new}"))
+                    .suggestion(
+                        "Usually caused by a cargo version change or toolchain switch; build with \
+                         one toolchain, pinned with rust-toolchain.toml",
+                    )
+            }
+            Self::CompilerChanged { old, new } => {
+                let mut parts = ExplanationParts::new(ReasonKind::TargetConfig, "Compiler changed");
+                if let (Some(old), Some(new)) = (old, new) {
+                    parts = parts.detail(format!("{old} -> {new}"));
+                }
+                parts
+                    .suggestion(
+                        "A different rustc was used than in the previous build, typically after \
+                         `rustup update` or a toolchain switch with `rustup default`, \
+                         `+toolchain` or a rust-toolchain.toml in another directory",
+                    )
+                    .suggestion(
+                        "Every crate is rebuilt for the new compiler; pin one toolchain for all \
+                         builds with rust-toolchain.toml",
+                    )
+            }
+            Self::Unknown(raw) => {
+                ExplanationParts::new(ReasonKind::Unknown, "Unrecognized rebuild reason")
+                    .detail(raw.clone())
+                    .suggestion("Please report this log format so it can be parsed")
+            }
+        }
+    }
+}
+
+/// Building blocks of an explanation: a headline, supporting details and
+/// suggestions
+struct ExplanationParts {
+    kind: ReasonKind,
+    title: String,
+    details: Vec<String>,
+    root_cause: Option<String>,
+    suggestions: Vec<String>,
+}
+
+impl ExplanationParts {
+    fn new(kind: ReasonKind, title: impl Into<String>) -> Self {
+        Self {
+            kind,
+            title: title.into(),
+            details: Vec::new(),
+            root_cause: None,
+            suggestions: Vec::new(),
+        }
+    }
+
+    fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.details.push(detail.into());
+        self
+    }
+
+    fn root_cause(mut self, root_cause: impl Into<String>) -> Self {
+        self.root_cause = Some(root_cause.into());
+        self
+    }
+
+    fn suggestion(mut self, suggestion: impl Into<String>) -> Self {
+        self.suggestions.push(suggestion.into());
+        self
+    }
+
+    fn build(self, icons: IconStyle) -> String {
+        let mut lines = vec![format!("{} {}", self.kind.icon(icons), self.title)];
+        lines.extend(
+            self.details
+                .into_iter()
+                .map(|detail| format!("   {detail}")),
+        );
+        if let Some(root_cause) = self.root_cause {
+            lines.push(match icons {
+                IconStyle::Emoji => format!("   🔍 Root cause: {root_cause}"),
+                IconStyle::Ascii => format!("   Root cause: {root_cause}"),
+            });
+        }
+
+        if !self.suggestions.is_empty() {
+            lines.push(match icons {
+                IconStyle::Emoji => "   💡 Suggestions:".to_string(),
+                IconStyle::Ascii => "   Suggestions:".to_string(),
+            });
+            lines.extend(
+                self.suggestions
+                    .into_iter()
+                    .map(|suggestion| format!("      - {suggestion}")),
+            );
+        }
+
+        lines.join("\n")
+    }
+}
+
+/// Variables describing the shell or terminal session rather than the build.
+/// A rebuild caused by one of them means a build script watches too much.
+const BUILD_IRRELEVANT_ENV_VARS: &[&str] = &[
+    "COLORTERM",
+    "COLUMNS",
+    "DISPLAY",
+    "LINES",
+    "OLDPWD",
+    "PWD",
+    "SHELL",
+    "SHLVL",
+    "TERM",
+    "TERM_PROGRAM",
+    "TERM_PROGRAM_VERSION",
+    "WINDOWID",
+];
+
+fn explain_env_var_change(name: &str, old: Option<&str>, new: Option<&str>) -> ExplanationParts {
+    let parts = ExplanationParts::new(
+        ReasonKind::EnvVar,
+        format!("Environment variable {name} changed"),
+    )
+    .detail(describe_env_change(old, new));
+
+    if BUILD_IRRELEVANT_ENV_VARS.contains(&name) {
+        return parts.suggestion(format!(
+            "{name} does not affect builds: a build script is likely over-subscribing to this \
+             variable; check its rerun-if-env-changed directives"
+        ));
+    }
+
+    parts
+        .suggestion(format!(
+            "Keep {name} identical between builds, for example by setting it in \
+             .cargo/config.toml under [env]"
+        ))
+        .suggestion(format!(
+            "Find the build script that prints cargo:rerun-if-env-changed={name}"
+        ))
+}
+
+fn explain_dependency_change(
+    name: &str,
+    old_fingerprint: &str,
+    new_fingerprint: &str,
+    context: Option<&DependencyChangeContext>,
+) -> ExplanationParts {
+    // Enough digits to tell fingerprints apart across runs
+    let short = |fingerprint: &str| fingerprint.chars().take(8).collect::<String>();
+
+    let mut parts = ExplanationParts::new(
+        ReasonKind::Dependency,
+        format!("Dependency {name} was rebuilt"),
+    );
+
+    if !old_fingerprint.is_empty() && !new_fingerprint.is_empty() {
+        parts = parts.detail(format!(
+            "Fingerprint: {} -> {}",
+            short(old_fingerprint),
+            short(new_fingerprint)
+        ));
+    }
+
+    if let Some(context) = context {
+        if let Some(package_id) = &context.package_id {
+            parts = parts.detail(format!("Package: {package_id}"));
+        }
+        if let Some(target_type) = &context.target_type {
+            parts = parts.detail(format!("Target: {target_type}"));
+        }
+        if let Some(fingerprint_file) = &context.fingerprint_file {
+            parts = parts.detail(format!("Fingerprint file: {fingerprint_file}"));
+        }
+        if let Some(root_cause) = &context.root_cause {
+            parts = parts.root_cause(root_cause.clone());
+        }
+    }
+
+    let is_build_script = context
+        .and_then(|context| context.target_type.as_deref())
+        .is_some_and(|target| target.starts_with("build-script"));
+    if is_build_script {
+        parts = parts
+            .suggestion(
+                "This build script reruns when a variable from its cargo:rerun-if-env-changed \
+                 lines or a path from its cargo:rerun-if-changed lines changes",
+            )
+            .suggestion(
+                "A build script without rerun-if-* directives reruns on any change in its \
+                 package; add rerun-if-changed=build.rs to narrow it down",
+            );
+    } else if name.ends_with("-sys") || name.ends_with("_sys") {
+        parts = parts.suggestion(format!(
+            "{name} wraps a native library; its build script reruns when the C toolchain, \
+             pkg-config or linker environment changes"
+        ));
+    }
+
+    parts
+        .suggestion(format!(
+            "If {name} is a registry dependency whose version keeps moving, pin it with `cargo \
+             update -p {name} --precise <version>` and build with --locked in CI"
+        ))
+        .suggestion(format!("Look at why {name} itself was rebuilt"))
+}
+
+fn explain_rustflags_change(old: &[String], new: &[String]) -> ExplanationParts {
+    let sorted = |flags: &[String]| {
+        let mut flags = flags.to_vec();
+        flags.sort();
+        flags
+    };
+
+    let mut parts = ExplanationParts::new(ReasonKind::Rustflags, "RUSTFLAGS changed")
+        .detail(format!("old: {}", old.join(" ")))
+        .detail(format!("new: {}", new.join(" ")));
+    if old != new && sorted(old) == sorted(new) {
+        parts = parts.detail(
+            "Flags are identical but reordered; consider sorting RUSTFLAGS to avoid rebuilds",
+        );
+    }
+
+    parts
+        .suggestion(
+            "Set flags in one place (build.rustflags in .cargo/config.toml) instead of the \
+             environment",
+        )
+        .suggestion(
+            "Editors and other tools running cargo with different flags should use a separate \
+             target directory",
+        )
+}
+
+fn explain_file_change(path: &str, reference: Option<&str>) -> ExplanationParts {
+    let mut parts = if is_generated_source(path, reference) {
+        ExplanationParts::new(
+            ReasonKind::GeneratedFile,
+            format!("Generated file changed: {path}"),
+        )
+        .suggestion(
+            "A build script rewrote this file; only write outputs when their content changes",
+        )
+    } else {
+        ExplanationParts::new(
+            ReasonKind::SourceFile,
+            format!("Source file changed: {path}"),
+        )
+        .suggestion("Expected after editing the file")
+    };
+
+    if let Some(reference) = reference {
+        parts = parts.detail(format!("Compared against: {reference}"));
+    }
+
+    parts
+}
+
+fn describe_env_change(old: Option<&str>, new: Option<&str>) -> String {
+    match (old, new) {
+        (Some(old), Some(new)) => format!("'{old}' -> '{new}'"),
+        (Some(old), None) => format!("'{old}' -> unset"),
+        (None, Some(new)) => format!("unset -> '{new}'"),
+        (None, None) => "changed".to_string(),
+    }
+}
+
+/// Last two components of a path, e.g. `src/main.rs`
+fn short_path(path: &str) -> String {
+    path.split('/')
+        .rev()
+        .take(2)
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Returns true for Rust files a build script generated into its `OUT_DIR`
+/// rather than edited ones. When the fingerprint `reference` shows where the
+/// target directory is, the file must be in its `build/` directory; otherwise
+/// the path must have the `build/<unit>/out` layout of an `OUT_DIR`.
+fn is_generated_source(path: &str, reference: Option<&str>) -> bool {
+    let path = Path::new(path);
+    if path.extension().is_none_or(|ext| ext != "rs") {
+        return false;
+    }
+    reference.and_then(profile_dir).map_or_else(
+        || is_in_out_dir(path),
+        |profile_dir| path.starts_with(profile_dir.join("build")),
+    )
+}
+
+fn is_in_out_dir(path: &Path) -> bool {
+    let names: Vec<_> = path.components().map(Component::as_os_str).collect();
+    names
+        .windows(3)
+        .any(|window| window[0] == "build" && window[2] == "out")
+}
+
+/// Profile directory of a target directory, such as `target/debug`, holding
+/// the `.fingerprint` directory that `reference` lies in
+fn profile_dir(reference: &str) -> Option<&Path> {
+    Path::new(reference)
+        .ancestors()
+        .find(|dir| dir.file_name().is_some_and(|name| name == ".fingerprint"))?
+        .parent()
+}
+
+/// Canonical order for deterministic output: by [`ReasonKind`], then by the
+/// displayed detail. The debug representation breaks remaining ties, so
+/// only equal reasons compare equal.
+impl Ord for RebuildReason {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.kind()
+            .cmp(&other.kind())
+            .then_with(|| self.to_string().cmp(&other.to_string()))
+            .then_with(|| format!("{self:?}").cmp(&format!("{other:?}")))
+    }
+}
+
+impl PartialOrd for RebuildReason {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
 impl Display for RebuildReason {
@@ -57,12 +673,7 @@ impl Display for RebuildReason {
                 old_value,
                 new_value,
             } => {
-                let change = match (old_value, new_value) {
-                    (Some(old), Some(new)) => format!("'{old}' -> '{new}'"),
-                    (Some(old), None) => format!("'{old}' -> unset"),
-                    (None, Some(new)) => format!("unset -> '{new}'"),
-                    (None, None) => "changed".to_string(),
-                };
+                let change = describe_env_change(old_value.as_deref(), new_value.as_deref());
                 write!(f, "env:{name} ({change})")
             }
             Self::UnitDependencyInfoChanged { name, .. } => write!(f, "dep:{name}"),
@@ -70,18 +681,30 @@ impl Display for RebuildReason {
             Self::FeaturesChanged { old, new } => write!(f, "features: {old} -> {new}"),
             Self::ProfileConfigurationChanged => write!(f, "profile changed"),
             Self::TargetConfigurationChanged => write!(f, "target config changed"),
-            Self::FileChanged { path } => {
-                let short_path = path
-                    .split('/')
-                    .rev()
-                    .take(2)
-                    .collect::<Vec<_>>()
-                    .into_iter()
-                    .rev()
-                    .collect::<Vec<_>>()
-                    .join("/");
-                write!(f, "file:{short_path}")
+            Self::FileChanged { path, reference } => {
+                let label = if is_generated_source(path, reference.as_deref()) {
+                    "generated"
+                } else {
+                    "file"
+                };
+                write!(f, "{label}:{}", short_path(path))?;
+                if let Some(reference) = reference {
+                    write!(f, " (vs {})", short_path(reference))?;
+                }
+                Ok(())
+            }
+            Self::MissingFile { path } => write!(f, "missing:{}", short_path(path)),
+            Self::DepInfoOutputChanged { new, .. } => {
+                write!(f, "dep-info moved:{}", short_path(new))
             }
+            Self::PrecalculatedComponentsChanged { .. } => {
+                write!(f, "precalculated components changed")
+            }
+            Self::LocalFingerprintTypeChanged { new, .. } => {
+                write!(f, "fingerprint type:{new}")
+            }
+            Self::CompilerChanged { new: Some(new), .. } => write!(f, "rustc changed:{new}"),
+            Self::CompilerChanged { new: None, .. } => write!(f, "rustc changed"),
             Self::Unknown(msg) => write!(f, "unknown:{msg}"),
         }
     }
@@ -91,6 +714,27 @@ impl Display for RebuildReason {
 mod tests {
     use super::*;
 
+    #[test]
+    fn grades_reasons_by_severity() {
+        let source_edit = RebuildReason::FileChanged {
+            path: "src/main.rs".to_string(),
+            reference: None,
+        };
+        let target_dir = RebuildReason::EnvVarChanged {
+            name: "CARGO_TARGET_DIR".to_string(),
+            old_value: Some("target".to_string()),
+            new_value: Some("/tmp/target".to_string()),
+        };
+
+        assert_eq!(source_edit.severity(), Severity::Info);
+        assert_eq!(target_dir.severity(), Severity::Warn);
+        assert_eq!(
+            RebuildReason::Unknown("Mystery".to_string()).severity(),
+            Severity::Error
+        );
+        assert_eq!("warn".parse(), Ok(Severity::Warn));
+    }
+
     #[test]
     fn displays_environment_variable_changes() {
         let env_change = RebuildReason::EnvVarChanged {
@@ -110,11 +754,31 @@ mod tests {
         };
 
         assert!(dep_change.to_string().contains("dep:rusqlite"));
+        let explanation = dep_change.explanation();
+        assert!(
+            explanation.contains("pin it with `cargo update -p rusqlite"),
+            "Expected a pin suggestion naming rusqlite, got: {explanation}"
+        );
 
         let target_change = RebuildReason::TargetConfigurationChanged;
         assert!(target_change.to_string().contains("target config changed"));
     }
 
+    #[test]
+    fn explanation_without_suggestions_keeps_title_and_details() {
+        let env_change = RebuildReason::EnvVarChanged {
+            name: "CC".to_string(),
+            old_value: Some("gcc".to_string()),
+            new_value: None,
+        };
+
+        let full = env_change.explanation_with(IconStyle::Ascii);
+        let terse = env_change.explanation_without_suggestions(IconStyle::Ascii);
+        assert!(full.contains("Suggestions:"), "{full}");
+        assert!(!terse.contains("Suggestions:"), "{terse}");
+        assert!(full.starts_with(&terse), "{terse}");
+    }
+
     #[test]
     fn displays_features_and_profile_changes() {
         let features_change = RebuildReason::FeaturesChanged {
@@ -140,6 +804,7 @@ mod tests {
                 package_id: Some("libz-sys v1.1.23".to_string()),
                 target_type: Some("build-script-build".to_string()),
                 root_cause: Some("CC environment variable changed".to_string()),
+                fingerprint_file: None,
             }),
         };
 
@@ -160,4 +825,195 @@ mod tests {
 
         assert!(rustflags_change.to_string().contains("rustflags changed"));
     }
+
+    #[test]
+    fn classifies_files_under_target_as_generated() {
+        let generated = RebuildReason::FileChanged {
+            path: "/home/user/project/target/debug/build/foo-1a2b3c/out/generated.rs".to_string(),
+            reference: None,
+        };
+
+        assert_eq!(generated.kind(), ReasonKind::GeneratedFile);
+        assert!(generated.is_avoidable());
+        assert!(generated.to_string().starts_with("generated:"));
+
+        let source = RebuildReason::FileChanged {
+            path: "/home/user/project/src/main.rs".to_string(),
+            reference: None,
+        };
+
+        assert_eq!(source.kind(), ReasonKind::SourceFile);
+        assert!(!source.is_avoidable());
+        assert!(source.to_string().starts_with("file:"));
+    }
+
+    #[test]
+    fn project_below_a_directory_named_target_is_source() {
+        let reference = "/srv/target/proj/target/debug/.fingerprint/proj-1a2b/dep-lib-proj";
+        let source = RebuildReason::FileChanged {
+            path: "/srv/target/proj/src/lib.rs".to_string(),
+            reference: Some(reference.to_string()),
+        };
+        assert_eq!(source.kind(), ReasonKind::SourceFile);
+        assert!(!source.is_avoidable());
+
+        let unanchored = RebuildReason::FileChanged {
+            path: "/srv/target/proj/src/lib.rs".to_string(),
+            reference: None,
+        };
+        assert_eq!(unanchored.kind(), ReasonKind::SourceFile);
+
+        let generated = RebuildReason::FileChanged {
+            path: "/srv/target/proj/target/debug/build/proj-3c4d/out/bindings.rs".to_string(),
+            reference: Some(reference.to_string()),
+        };
+        assert_eq!(generated.kind(), ReasonKind::GeneratedFile);
+    }
+
+    #[test]
+    fn file_change_mentions_reference() {
+        let file_change = RebuildReason::FileChanged {
+            path: "/tmp/project/src/main.rs".to_string(),
+            reference: Some(
+                "/tmp/project/target/debug/.fingerprint/app-0123/dep-bin-app".to_string(),
+            ),
+        };
+
+        assert_eq!(
+            file_change.to_string(),
+            "file:src/main.rs (vs app-0123/dep-bin-app)"
+        );
+
+        let explanation = file_change.explanation();
+        assert!(explanation.contains("Source file changed: /tmp/project/src/main.rs"));
+        assert!(explanation.contains(
+            "Compared against: /tmp/project/target/debug/.fingerprint/app-0123/dep-bin-app"
+        ));
+    }
+
+    #[test]
+    fn explains_missing_file() {
+        let missing = RebuildReason::MissingFile {
+            path: "/tmp/x/target/debug/deps/libfoo.rlib".to_string(),
+        };
+
+        assert_eq!(missing.to_string(), "missing:deps/libfoo.rlib");
+        assert!(missing.explanation().contains("partially cleaned"));
+    }
+
+    #[test]
+    fn ascii_explanation_has_no_emoji() {
+        let reason = RebuildReason::UnitDependencyInfoChanged {
+            name: "openssl-sys".to_string(),
+            old_fingerprint: "1".to_string(),
+            new_fingerprint: "2".to_string(),
+            context: Some(DependencyChangeContext {
+                package_id: None,
+                target_type: None,
+                root_cause: Some("env:OPENSSL_DIR".to_string()),
+                fingerprint_file: None,
+            }),
+        };
+
+        let explanation = reason.explanation_with(IconStyle::Ascii);
+        assert!(
+            explanation.starts_with("[DEP] Dependency openssl-sys was rebuilt"),
+            "unexpected headline: {explanation}"
+        );
+        assert!(
+            explanation.contains("Root cause: env:OPENSSL_DIR"),
+            "root cause missing: {explanation}"
+        );
+        assert!(explanation.is_ascii(), "non-ASCII output: {explanation}");
+    }
+
+    #[test]
+    fn build_script_dependency_change_suggests_rerun_directives() {
+        let reason = RebuildReason::UnitDependencyInfoChanged {
+            name: "cc".to_string(),
+            old_fingerprint: "1".to_string(),
+            new_fingerprint: "2".to_string(),
+            context: Some(DependencyChangeContext {
+                package_id: Some("openssl-sys v0.9.0".to_string()),
+                target_type: Some("build-script-build".to_string()),
+                root_cause: None,
+                fingerprint_file: None,
+            }),
+        };
+
+        let explanation = reason.explanation();
+
+        assert!(
+            explanation.contains("rerun-if-env-changed")
+                && explanation.contains("rerun-if-changed=build.rs"),
+            "Expected build script advice: {explanation}"
+        );
+    }
+
+    #[test]
+    fn dependency_explanation_shows_fingerprint_prefixes() {
+        let change = |old: &str, new: &str| RebuildReason::UnitDependencyInfoChanged {
+            name: "libsqlite3-sys".to_string(),
+            old_fingerprint: old.to_string(),
+            new_fingerprint: new.to_string(),
+            context: None,
+        };
+
+        let explanation = change("3226094009085469434", "17151236291452738470").explanation();
+        assert!(
+            explanation.contains("Fingerprint: 32260940 -> 17151236"),
+            "Expected both fingerprint prefixes: {explanation}"
+        );
+
+        let explanation = change("", "17151236291452738470").explanation();
+        assert!(
+            !explanation.contains("Fingerprint"),
+            "Expected no fingerprints without an old value: {explanation}"
+        );
+    }
+
+    #[test]
+    fn rustflags_explanation_notices_reordering() {
+        let flags = |flags: &[&str]| flags.iter().map(ToString::to_string).collect::<Vec<_>>();
+        let reordered = RebuildReason::RustflagsChanged {
+            old: flags(&["-C", "target-cpu=native", "--cfg", "tokio_unstable"]),
+            new: flags(&["--cfg", "tokio_unstable", "-C", "target-cpu=native"]),
+        };
+        let different = RebuildReason::RustflagsChanged {
+            old: flags(&["-C", "target-cpu=native"]),
+            new: flags(&["-C", "opt-level=3"]),
+        };
+
+        assert!(
+            reordered.explanation().contains("identical but reordered"),
+            "Expected the reorder note: {}",
+            reordered.explanation()
+        );
+        assert!(
+            !different.explanation().contains("reordered"),
+            "Different flags are not a reordering: {}",
+            different.explanation()
+        );
+    }
+
+    #[test]
+    fn flags_build_scripts_watching_terminal_variables() {
+        let term = RebuildReason::EnvVarChanged {
+            name: "TERM".to_string(),
+            old_value: Some("xterm-256color".to_string()),
+            new_value: Some("screen".to_string()),
+        };
+        let explanation = term.explanation();
+        assert!(
+            explanation.contains("over-subscribing to this variable"),
+            "{explanation}"
+        );
+
+        let cc = RebuildReason::EnvVarChanged {
+            name: "CC".to_string(),
+            old_value: None,
+            new_value: Some("clang".to_string()),
+        };
+        assert!(!cc.explanation().contains("over-subscribing"));
+    }
 }
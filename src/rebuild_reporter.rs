@@ -0,0 +1,1455 @@
+//! Renderings of the rebuild analysis
+//!
+//! Holds the default root cause listing as well as the additional reports
+//! that can be requested on the command line.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Write as FmtWrite,
+    io::{self, Write},
+    iter,
+    path::Path,
+};
+
+use serde::Serialize;
+
+use crate::{
+    fingerprint_parser::ParsedRebuildEntry,
+    rebuild_graph::{
+        PackageTarget, RebuildGraph, RebuildNode, RootCauseChain, TargetKind, TargetKinds,
+    },
+    rebuild_reason::{IconStyle, ReasonKind, RebuildReason, Severity},
+};
+
+const HISTOGRAM_WIDTH: usize = 40;
+
+/// Rebuilt packages of a root cause are listed one per line up to this many;
+/// longer lists collapse into a count unless expanded
+const COLLAPSE_AFTER: usize = 5;
+
+/// Presentation options for the text report
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TextStyle<'a> {
+    /// Highlight packages and reasons with ANSI escape codes
+    pub color: bool,
+    /// Icons used in explanations
+    pub icons: IconStyle,
+    /// List every rebuilt package instead of collapsing long lists
+    pub expand: bool,
+    /// Show changed files relative to this project root when they are under
+    /// it; `None` keeps the paths cargo logged
+    pub project_root: Option<&'a Path>,
+    /// Number of crates in the workspace and its dependencies, to show each
+    /// root cause's impact as a share of it
+    pub total_crates: Option<usize>,
+    /// Leave the suggestions out of explanations
+    pub hide_suggestions: bool,
+    /// Kinds of the project's targets from `cargo metadata`, when known
+    pub target_kinds: Option<&'a TargetKinds>,
+}
+
+impl TextStyle<'_> {
+    fn package(self, package: &PackageTarget) -> String {
+        if self.color {
+            format!("\x1b[1m{package}\x1b[0m")
+        } else {
+            package.to_string()
+        }
+    }
+
+    fn reason(self, reason: &RebuildReason) -> String {
+        if !self.color {
+            return reason.to_string();
+        }
+
+        let code = match reason.kind() {
+            ReasonKind::EnvVar => "33",
+            ReasonKind::SourceFile | ReasonKind::GeneratedFile | ReasonKind::MissingFile => "36",
+            ReasonKind::Dependency => "35",
+            ReasonKind::Unknown => "31",
+            ReasonKind::Rustflags
+            | ReasonKind::Features
+            | ReasonKind::Profile
+            | ReasonKind::TargetConfig => "34",
+        };
+        format!("\x1b[{code}m{reason}\x1b[0m")
+    }
+
+    fn severity(self, severity: Severity) -> String {
+        let code = match severity {
+            Severity::Info => "32",
+            Severity::Warn => "33",
+            Severity::Error => "31",
+        };
+        if self.color {
+            format!("\x1b[{code}m({severity})\x1b[0m")
+        } else {
+            format!("({severity})")
+        }
+    }
+
+    fn target_kind(self, package: &PackageTarget) -> TargetKind {
+        self.target_kinds
+            .map_or_else(|| package.kind(), |kinds| kinds.kind(package))
+    }
+
+    fn explanation(self, reason: &RebuildReason) -> String {
+        let relative;
+        let reason = match (reason, self.project_root) {
+            (RebuildReason::FileChanged { path, reference }, Some(root)) => {
+                relative = RebuildReason::FileChanged {
+                    path: relative_path(path, root),
+                    reference: reference
+                        .as_deref()
+                        .map(|reference| relative_path(reference, root)),
+                };
+                &relative
+            }
+            _ => reason,
+        };
+        if self.hide_suggestions {
+            reason.explanation_without_suggestions(self.icons)
+        } else {
+            reason.explanation_with(self.icons)
+        }
+    }
+}
+
+/// `path` relative to `root`, or unchanged when it lies outside of it
+fn relative_path(path: &str, root: &Path) -> String {
+    Path::new(path).strip_prefix(root).map_or_else(
+        |_| path.to_string(),
+        |relative| relative.display().to_string(),
+    )
+}
+
+/// Print root causes with their explanations and the packages they rebuilt.
+/// `omitted` counts root causes left out of `chains`, which are summarized
+/// in a final line.
+pub fn print_root_causes(chains: &[&RootCauseChain], omitted: usize, style: TextStyle<'_>) {
+    if chains.is_empty() && omitted == 0 {
+        println!("No rebuild triggers detected.");
+        return;
+    }
+
+    let mut groups = group_by_changed_file(chains);
+    // Build script triggers first, they point at the file to investigate
+    groups.sort_by_key(
+        |group| !matches!(group.as_slice(), [chain] if build_script_trigger(chain).is_some()),
+    );
+    let total = groups.len() + omitted;
+    println!("\n{total} root cause{}:", if total == 1 { "" } else { "s" });
+
+    for group in groups {
+        let [chain] = group.as_slice() else {
+            print_shared_file_change(&group, style);
+            continue;
+        };
+        let root = &chain.root_cause;
+        println!(
+            "  {} {} {}",
+            style.package(&root.package),
+            style.reason(&root.reason),
+            style.severity(root.reason.severity())
+        );
+        if let Some(path) = build_script_trigger(chain) {
+            let path = style
+                .project_root
+                .map_or_else(|| path.to_string(), |root| relative_path(path, root));
+            println!("    build script trigger: {path}");
+        }
+        for line in style.explanation(&root.reason).lines() {
+            println!("    {line}");
+        }
+        if style.target_kind(&root.package) == TargetKind::ProcMacro {
+            println!("    Warning: this is a proc-macro; rebuilding it invalidates all dependents");
+        }
+        if let Some(total) = style.total_crates {
+            println!("    Impact: {}", chain.impact_fraction(total));
+        }
+        for line in affected_lines(&chain.affected_packages, style.expand) {
+            println!("    {line}");
+        }
+    }
+    if omitted > 0 {
+        println!("  (… and {omitted} more)");
+    }
+
+    let avoidable = chains
+        .iter()
+        .filter(|chain| chain.root_cause.reason.is_avoidable())
+        .count();
+    if avoidable > 0 {
+        println!(
+            "\n{avoidable} of these {} not caused by source edits and could be avoided.",
+            if avoidable == 1 { "is" } else { "are" }
+        );
+    }
+}
+
+/// Print how the root causes of a second analysis differ from the first:
+/// `added` were not found before, `removed` are no longer found
+pub fn print_trigger_delta<'a>(
+    added: impl IntoIterator<Item = &'a RebuildNode>,
+    removed: impl IntoIterator<Item = &'a RebuildNode>,
+    style: TextStyle<'_>,
+) {
+    let changes: Vec<_> = added
+        .into_iter()
+        .map(|node| ('+', node))
+        .chain(removed.into_iter().map(|node| ('-', node)))
+        .filter(|(_, node)| node.is_root_cause())
+        .collect();
+    if changes.is_empty() {
+        println!("\nNo rebuild triggers changed.");
+        return;
+    }
+
+    println!("\nChanges since the first analysis:");
+    for (sign, node) in changes {
+        println!(
+            "  {sign} {} {}",
+            style.package(&node.package),
+            style.reason(&node.reason)
+        );
+    }
+}
+
+/// One sentence naming the root cause that rebuilt the most units, given
+/// chains ranked by [`RebuildGraph::root_causes_by_impact`]. Among equally
+/// large chains a recognized reason wins over an unrecognized one.
+#[must_use]
+pub fn bottom_line(chains_by_impact: &[RootCauseChain]) -> String {
+    let Some(first) = chains_by_impact.first() else {
+        return "Bottom line: clean build, nothing was rebuilt.".to_string();
+    };
+    let top = chains_by_impact
+        .iter()
+        .take_while(|chain| chain.total_rebuilds() == first.total_rebuilds())
+        .find(|chain| chain.root_cause.reason.kind() != ReasonKind::Unknown)
+        .unwrap_or(first);
+    let rebuilds = top.total_rebuilds();
+    format!(
+        "Bottom line: {} in {} caused {rebuilds} rebuild{}.",
+        top.root_cause.reason.title(),
+        top.root_cause.package.name(),
+        if rebuilds == 1 { "" } else { "s" }
+    )
+}
+
+/// Rebuilt nodes grouped by package, in order of first appearance
+#[must_use]
+pub fn group_by_package<'a>(
+    nodes: impl IntoIterator<Item = &'a RebuildNode>,
+) -> Vec<(&'a str, Vec<&'a RebuildNode>)> {
+    let mut groups: Vec<(&str, Vec<&RebuildNode>)> = Vec::new();
+    for node in nodes {
+        let name = node.package.name();
+        match groups.iter_mut().find(|(package, _)| *package == name) {
+            Some((_, targets)) => targets.push(node),
+            None => groups.push((name, vec![node])),
+        }
+    }
+    groups
+}
+
+/// Rebuilt nodes grouped by the kind of their reason, in [`ReasonKind`] order
+#[must_use]
+pub fn group_by_reason_kind<'a>(
+    nodes: impl IntoIterator<Item = &'a RebuildNode>,
+) -> BTreeMap<ReasonKind, Vec<&'a RebuildNode>> {
+    let mut groups: BTreeMap<_, Vec<_>> = BTreeMap::new();
+    for node in nodes {
+        groups.entry(node.reason.kind()).or_default().push(node);
+    }
+    groups
+}
+
+/// Print every rebuilt package with the reason each of its targets was
+/// rebuilt for
+pub fn print_by_package<'a>(
+    nodes: impl IntoIterator<Item = &'a RebuildNode>,
+    style: TextStyle<'_>,
+) {
+    let groups = group_by_package(nodes);
+    if groups.is_empty() {
+        println!("No rebuild triggers detected.");
+        return;
+    }
+    println!(
+        "\n{} rebuilt package{}:",
+        groups.len(),
+        if groups.len() == 1 { "" } else { "s" }
+    );
+    for (package, targets) in groups {
+        println!(
+            "  {package} ({} unit{})",
+            targets.len(),
+            if targets.len() == 1 { "" } else { "s" }
+        );
+        for node in targets {
+            println!(
+                "    {}: {}",
+                node.package.target.as_deref().unwrap_or("(unknown target)"),
+                style.reason(&node.reason)
+            );
+        }
+    }
+}
+
+/// Print a header with the number of rebuilt units for each reason kind,
+/// followed by those units
+pub fn print_by_reason_kind<'a>(
+    nodes: impl IntoIterator<Item = &'a RebuildNode>,
+    style: TextStyle<'_>,
+) {
+    let groups = group_by_reason_kind(nodes);
+    if groups.is_empty() {
+        println!("No rebuild triggers detected.");
+    }
+    for (kind, nodes) in groups {
+        println!(
+            "\n{kind} ({} unit{}):",
+            nodes.len(),
+            if nodes.len() == 1 { "" } else { "s" }
+        );
+        for node in nodes {
+            println!(
+                "  {} {}",
+                style.package(&node.package),
+                style.reason(&node.reason)
+            );
+        }
+    }
+}
+
+/// Rebuilt nodes grouped by package, in order of first appearance, for the
+/// packages in which more than one target was rebuilt
+#[must_use]
+pub fn targets_by_package<'a>(
+    nodes: impl IntoIterator<Item = &'a RebuildNode>,
+) -> Vec<(&'a str, Vec<&'a RebuildNode>)> {
+    let mut groups = group_by_package(nodes);
+    groups.retain(|(_, targets)| {
+        targets
+            .iter()
+            .any(|node| node.package.target != targets[0].package.target)
+    });
+    groups
+}
+
+/// Print each package in which several targets were rebuilt with the reason
+/// of every target, e.g. to reveal that only its tests were rebuilt
+pub fn print_targets_by_package(groups: &[(&str, Vec<&RebuildNode>)], style: TextStyle<'_>) {
+    if groups.is_empty() {
+        return;
+    }
+    println!("\nTargets rebuilt per package:");
+    for (package, targets) in groups {
+        println!("  {package}");
+        for node in targets {
+            println!(
+                "    {}: {}",
+                node.package.target.as_deref().unwrap_or("(unknown target)"),
+                style.reason(&node.reason)
+            );
+        }
+    }
+}
+
+/// Root causes printed as one entry: a single chain, or all chains whose root
+/// cause is a change to the same file, in order of first appearance
+fn group_by_changed_file<'a>(chains: &[&'a RootCauseChain]) -> Vec<Vec<&'a RootCauseChain>> {
+    let mut groups: Vec<Vec<&RootCauseChain>> = Vec::new();
+    for &chain in chains {
+        let path = changed_file(&chain.root_cause.reason);
+        let shared = groups
+            .iter_mut()
+            .find(|group| path.is_some() && changed_file(&group[0].root_cause.reason) == path);
+        match shared {
+            Some(group) => group.push(chain),
+            None => groups.push(vec![chain]),
+        }
+    }
+    groups
+}
+
+// Changed file that reran a build script: a `build.rs` itself, or a file
+// watched by a `build-script-build` unit
+fn build_script_trigger(chain: &RootCauseChain) -> Option<&str> {
+    let path = changed_file(&chain.root_cause.reason)?;
+    let is_build_rs = Path::new(path)
+        .file_name()
+        .is_some_and(|name| name == "build.rs");
+    (is_build_rs || chain.root_cause.package.kind() == TargetKind::BuildScript).then_some(path)
+}
+
+fn changed_file(reason: &RebuildReason) -> Option<&str> {
+    match reason {
+        RebuildReason::FileChanged { path, .. } => Some(path),
+        _ => None,
+    }
+}
+
+/// Print a file that is the root cause in several packages once, followed by
+/// those packages. The fingerprint each package compared against differs, so
+/// it is left out.
+fn print_shared_file_change(group: &[&RootCauseChain], style: TextStyle<'_>) {
+    let Some(path) = group
+        .first()
+        .and_then(|chain| changed_file(&chain.root_cause.reason))
+    else {
+        return;
+    };
+    let reason = RebuildReason::FileChanged {
+        path: path.to_string(),
+        reference: None,
+    };
+    println!(
+        "  {} in {} packages {}",
+        style.reason(&reason),
+        group.len(),
+        style.severity(reason.severity())
+    );
+    for line in style.explanation(&reason).lines() {
+        println!("    {line}");
+    }
+    println!("    Changed in:");
+    for chain in group {
+        println!("      - {}", style.package(&chain.root_cause.package));
+    }
+
+    let mut seen = HashSet::new();
+    let affected: Vec<_> = group
+        .iter()
+        .flat_map(|chain| &chain.affected_packages)
+        .filter(|node| seen.insert(&node.package))
+        .cloned()
+        .collect();
+    for line in affected_lines(&affected, style.expand) {
+        println!("    {line}");
+    }
+}
+
+/// Lines naming the packages a root cause rebuilt: one per package, or a
+/// single line with a count for long lists unless `expand` is set
+fn affected_lines(affected: &[RebuildNode], expand: bool) -> Vec<String> {
+    if affected.is_empty() {
+        return Vec::new();
+    }
+    if expand || affected.len() <= COLLAPSE_AFTER {
+        return iter::once("Also rebuilt:".to_string())
+            .chain(affected.iter().map(|node| format!("  - {}", node.package)))
+            .collect();
+    }
+
+    let shown = 3;
+    let names: Vec<_> = affected[..shown]
+        .iter()
+        .map(|node| node.package.to_string())
+        .collect();
+    vec![format!(
+        "Also rebuilt: {} and {} more ({} packages)",
+        names.join(", "),
+        affected.len() - shown,
+        affected.len()
+    )]
+}
+
+/// Hint printed when the rebuilds look like the target directory moved
+pub const RELOCATION_HINT: &str = "Your target directory appears to have moved. Cargo \
+                                   fingerprints record absolute paths, so moving the project or \
+                                   changing CARGO_TARGET_DIR rebuilds everything.";
+
+/// Fewest nodes with paths pointing at another location that count as a
+/// moved target directory
+const RELOCATION_MIN_NODES: usize = 2;
+
+/// Returns [`RELOCATION_HINT`] if `CARGO_TARGET_DIR` changed or several
+/// nodes point at paths that moved. `project_root` is the canonical root of
+/// the analyzed project; without it only dep-info paths are compared.
+#[must_use]
+pub fn relocation_hint(nodes: &[RebuildNode], project_root: Option<&Path>) -> Option<&'static str> {
+    let target_dir_changed = nodes.iter().any(|node| {
+        matches!(&node.reason, RebuildReason::EnvVarChanged { name, .. } if name == "CARGO_TARGET_DIR")
+    });
+    let moved_paths = nodes
+        .iter()
+        .filter(|node| points_elsewhere(&node.reason, project_root))
+        .count();
+
+    (target_dir_changed || moved_paths >= RELOCATION_MIN_NODES).then_some(RELOCATION_HINT)
+}
+
+/// Whether a reason shows a changed directory prefix: a dep-info file that
+/// is now written to another directory, or a source file the fingerprint
+/// recorded outside the analyzed project
+fn points_elsewhere(reason: &RebuildReason, project_root: Option<&Path>) -> bool {
+    match reason {
+        RebuildReason::DepInfoOutputChanged { old, new } => {
+            Path::new(old).parent() != Path::new(new).parent()
+        }
+        RebuildReason::FileChanged { path, .. } | RebuildReason::MissingFile { path } => {
+            let path = Path::new(path);
+            project_root.is_some_and(|root| path.is_absolute() && !path.starts_with(root))
+        }
+        _ => false,
+    }
+}
+
+/// Render root cause chains as a Mermaid `flowchart TD` with an edge from
+/// each root cause to every package it rebuilt
+#[must_use]
+pub fn to_mermaid(chains: &[RootCauseChain]) -> String {
+    let mut output = String::from("flowchart TD\n");
+
+    for chain in chains {
+        let root = &chain.root_cause;
+        let root_id = mermaid_id(&root.package);
+        let _ = writeln!(
+            output,
+            "    {root_id}[\"{}<br/>{}\"]",
+            mermaid_label(&root.package.to_string()),
+            mermaid_label(&root.reason.to_string())
+        );
+
+        for affected in &chain.affected_packages {
+            let affected_id = mermaid_id(&affected.package);
+            let _ = writeln!(
+                output,
+                "    {affected_id}[\"{}\"]",
+                mermaid_label(&affected.package.to_string())
+            );
+            let _ = writeln!(output, "    {root_id} --> {affected_id}");
+        }
+    }
+
+    output
+}
+
+/// Render root cause chains as a Markdown document for pasting into issues
+/// and pull requests
+#[must_use]
+pub fn to_markdown(chains: &[RootCauseChain]) -> String {
+    let total: usize = chains.iter().map(RootCauseChain::total_rebuilds).sum();
+    let mut output = format!(
+        "## {total} rebuild{} from {} root cause{}\n",
+        if total == 1 { "" } else { "s" },
+        chains.len(),
+        if chains.len() == 1 { "" } else { "s" }
+    );
+
+    for chain in chains {
+        let root = &chain.root_cause;
+        let _ = write!(
+            output,
+            "\n- **{}**: `{}`\n\n  ```text\n",
+            root.package, root.reason
+        );
+        for line in root.reason.explanation().lines() {
+            let _ = writeln!(output, "  {line}");
+        }
+        output.push_str("  ```\n");
+
+        if !chain.affected_packages.is_empty() {
+            output.push_str("\n  - Also rebuilt:\n");
+            for affected in &chain.affected_packages {
+                let _ = writeln!(output, "    - {}", affected.package);
+            }
+        }
+    }
+
+    output
+}
+
+/// Render root cause chains as a `JUnit` `<testsuite>` for CI dashboards. Each
+/// root cause is a `<testcase>` named by its package; it fails with the
+/// explanation as body when `fails` accepts its reason kind.
+#[must_use]
+pub fn to_junit(chains: &[RootCauseChain], fails: impl Fn(ReasonKind) -> bool) -> String {
+    let failures = chains
+        .iter()
+        .filter(|chain| fails(chain.root_cause.reason.kind()))
+        .count();
+    let mut output = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuite name=\"cargo-frequent\" \
+         tests=\"{}\" failures=\"{failures}\">\n",
+        chains.len()
+    );
+
+    for chain in chains {
+        let root = &chain.root_cause;
+        let kind = root.reason.kind();
+        let name = xml_escape(&root.package.to_string());
+        if fails(kind) {
+            let _ = write!(
+                output,
+                "  <testcase classname=\"{kind}\" name=\"{name}\">\n    <failure type=\"{kind}\" \
+                 message=\"{}\">{}</failure>\n  </testcase>\n",
+                xml_escape(&root.reason.to_string()),
+                xml_escape(&root.reason.explanation_with(IconStyle::Ascii))
+            );
+        } else {
+            let _ = writeln!(output, "  <testcase classname=\"{kind}\" name=\"{name}\"/>");
+        }
+    }
+
+    output.push_str("</testsuite>\n");
+    output
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Mermaid node identifiers may only contain alphanumerics and underscores
+fn mermaid_id(package: &PackageTarget) -> String {
+    let raw = package.target.as_ref().map_or_else(
+        || package.package_id.clone(),
+        |target| format!("{}_{target}", package.package_id),
+    );
+    raw.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+fn mermaid_label(text: &str) -> String {
+    text.replace('"', "#quot;")
+}
+
+/// Write one CSV row per node with the columns
+/// `package,target,reason_kind,detail,is_root_cause`
+pub fn write_csv<'a, W: Write>(
+    nodes: impl IntoIterator<Item = &'a RebuildNode>,
+    w: &mut W,
+) -> io::Result<()> {
+    writeln!(w, "package,target,reason_kind,detail,is_root_cause")?;
+
+    for node in nodes {
+        writeln!(
+            w,
+            "{},{},{},{},{}",
+            csv_field(&node.package.package_id),
+            csv_field(node.package.target.as_deref().unwrap_or_default()),
+            node.reason.kind(),
+            csv_field(&reason_detail(&node.reason)),
+            node.is_root_cause()
+        )?;
+    }
+
+    Ok(())
+}
+
+/// One parsed rebuild trigger as a line of `--format ndjson`
+#[derive(Serialize)]
+struct RebuildEvent<'a> {
+    package: &'a str,
+    target: Option<&'a str>,
+    kind: ReasonKind,
+    detail: String,
+    /// Seconds since the start of the cargo invocation
+    timestamp: Option<f64>,
+}
+
+/// Write a parsed entry as a single line of JSON
+pub fn write_ndjson_event<W: Write>(entry: &ParsedRebuildEntry, w: &mut W) -> io::Result<()> {
+    let event = RebuildEvent {
+        package: &entry.package.package_id,
+        target: entry.package.target.as_deref(),
+        kind: entry.reason.kind(),
+        detail: reason_detail(&entry.reason),
+        timestamp: entry.timestamp.map(|timestamp| timestamp.as_secs_f64()),
+    };
+    serde_json::to_writer(&mut *w, &event)?;
+    writeln!(w)
+}
+
+/// The most specific piece of information about a reason, e.g. the variable
+/// name or file path
+fn reason_detail(reason: &RebuildReason) -> String {
+    match reason {
+        RebuildReason::EnvVarChanged { name, .. }
+        | RebuildReason::UnitDependencyInfoChanged { name, .. } => name.clone(),
+        RebuildReason::RustflagsChanged { new, .. } => new.join(" "),
+        RebuildReason::FeaturesChanged { old, new }
+        | RebuildReason::DepInfoOutputChanged { old, new }
+        | RebuildReason::PrecalculatedComponentsChanged { old, new }
+        | RebuildReason::LocalFingerprintTypeChanged { old, new }
+        | RebuildReason::CompilerChanged {
+            old: Some(old),
+            new: Some(new),
+        } => format!("{old} -> {new}"),
+        RebuildReason::FileChanged { path, .. } | RebuildReason::MissingFile { path } => {
+            path.clone()
+        }
+        RebuildReason::Unknown(raw) => raw.clone(),
+        RebuildReason::CompilerChanged { .. }
+        | RebuildReason::ProfileConfigurationChanged
+        | RebuildReason::TargetConfigurationChanged => String::new(),
+    }
+}
+
+/// Quote a CSV field if it contains a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// One line per root cause with space separated `key=value` pairs:
+/// `root_cause.package`, `root_cause.target`, `root_cause.kind`,
+/// `root_cause.severity`, `root_cause.reason` and `affected_count`. Values
+/// with spaces, quotes or `=` are double quoted.
+#[must_use]
+pub fn to_key_values(chains: &[RootCauseChain]) -> String {
+    let mut output = String::new();
+    for chain in chains {
+        let root = &chain.root_cause;
+        let _ = writeln!(
+            output,
+            "root_cause.package={} root_cause.target={} root_cause.kind={} root_cause.severity={} \
+             root_cause.reason={} affected_count={}",
+            kv_value(root.package.name()),
+            kv_value(root.package.target.as_deref().unwrap_or_default()),
+            root.reason.kind(),
+            root.reason.severity(),
+            kv_value(&root.reason.to_string()),
+            chain.affected_packages.len()
+        );
+    }
+    output
+}
+
+fn kv_value(value: &str) -> String {
+    if value.contains(char::is_whitespace) || value.contains(['"', '=']) {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// One line per reason, as ranked by
+/// [`RebuildGraph::reason_frequencies`], followed by the crates ranked by
+/// [`RebuildGraph::package_frequencies`]
+#[must_use]
+pub fn to_frequency_list(frequencies: &[(String, usize)], packages: &[(String, usize)]) -> String {
+    if frequencies.is_empty() {
+        return "No rebuild triggers detected.\n".to_string();
+    }
+
+    let plural = |count: usize| if count == 1 { "" } else { "s" };
+    let mut output = String::new();
+    for (reason, count) in frequencies {
+        let _ = writeln!(output, "{reason}: {count} unit{} rebuilt", plural(*count));
+    }
+    output.push_str("\nMost rebuilt crates:\n");
+    for (name, count) in packages {
+        let _ = writeln!(output, "  {name}: {count} rebuild{}", plural(*count));
+    }
+    output
+}
+
+/// Rebuilt units per distinct reason for `--format frequency --json`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FrequencyReport {
+    /// Most frequent first
+    pub entries: Vec<FrequencyEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FrequencyEntry {
+    pub reason_kind: ReasonKind,
+    pub detail: String,
+    pub count: usize,
+}
+
+impl FrequencyReport {
+    /// Group the rebuilt units of `graph` that pass `keep` as
+    /// [`RebuildGraph::reason_groups`] does
+    #[must_use]
+    pub fn from_graph(graph: &RebuildGraph, keep: impl Fn(&RebuildNode) -> bool) -> Self {
+        let entries = graph
+            .reason_groups(keep)
+            .into_iter()
+            .map(|(reason, count)| FrequencyEntry {
+                reason_kind: reason.kind(),
+                detail: reason_detail(reason),
+                count,
+            })
+            .collect();
+        Self { entries }
+    }
+}
+
+/// Layout of JSON output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum JsonStyle {
+    /// Indented over several lines
+    #[default]
+    Pretty,
+    /// A single line, for piping into other tools
+    Compact,
+}
+
+impl JsonStyle {
+    /// Serialize `value` in this layout
+    ///
+    /// # Errors
+    /// Returns error if serialization fails
+    pub fn serialize<T: Serialize + ?Sized>(self, value: &T) -> Result<String, serde_json::Error> {
+        match self {
+            Self::Pretty => serde_json::to_string_pretty(value),
+            Self::Compact => serde_json::to_string(value),
+        }
+    }
+}
+
+/// Serialize root cause chains to a JSON string
+///
+/// # Errors
+/// Returns error if serialization fails
+pub fn to_json(chains: &[RootCauseChain], style: JsonStyle) -> Result<String, serde_json::Error> {
+    style.serialize(chains)
+}
+
+/// Serialize rebuilt units to a flat JSON array
+///
+/// # Errors
+/// Returns error if serialization fails
+pub fn nodes_to_json(
+    nodes: &[&RebuildNode],
+    style: JsonStyle,
+) -> Result<String, serde_json::Error> {
+    style.serialize(nodes)
+}
+
+/// Serialize rebuild trees to a JSON string
+///
+/// # Errors
+/// Returns error if serialization fails
+pub fn trees_to_json(trees: &[RebuildTree], style: JsonStyle) -> Result<String, serde_json::Error> {
+    style.serialize(trees)
+}
+
+/// A rebuilt unit together with the rebuilds it cascaded into
+#[derive(Debug, Clone, Serialize)]
+pub struct RebuildTree {
+    pub node: RebuildNode,
+    pub cascades: Vec<Self>,
+}
+
+impl RebuildTree {
+    /// Drop the cascades whose node fails `keep`, together with everything
+    /// nested below them
+    pub fn retain(&mut self, keep: &impl Fn(&RebuildNode) -> bool) {
+        self.cascades.retain(|cascade| keep(&cascade.node));
+        for cascade in &mut self.cascades {
+            cascade.retain(keep);
+        }
+    }
+
+    // Each node appears at most once per tree, which also stops cycles
+    fn grow(graph: &RebuildGraph, idx: usize, visited: &mut HashSet<usize>) -> Self {
+        let direct: Vec<_> = graph
+            .dependents(idx)
+            .iter()
+            .copied()
+            .filter(|&dependent| visited.insert(dependent))
+            .collect();
+        let cascades = direct
+            .into_iter()
+            .map(|dependent| Self::grow(graph, dependent, visited))
+            .collect();
+
+        Self {
+            node: graph.nodes()[idx].clone(),
+            cascades,
+        }
+    }
+}
+
+/// Tree of the root cause of `chain`, nesting each rebuilt unit under the
+/// unit whose change caused it
+#[must_use]
+pub fn cascade_tree(graph: &RebuildGraph, chain: &RootCauseChain) -> Option<RebuildTree> {
+    let idx = graph.index_of(&chain.root_cause)?;
+    Some(RebuildTree::grow(graph, idx, &mut HashSet::from([idx])))
+}
+
+/// Occurrence count of each reason kind over all parsed entries, including
+/// entries that the graph later deduplicates
+#[derive(Debug, Default)]
+pub struct ReasonHistogram {
+    counts: BTreeMap<ReasonKind, usize>,
+}
+
+impl ReasonHistogram {
+    pub fn record(&mut self, reason: &RebuildReason) {
+        *self.counts.entry(reason.kind()).or_default() += 1;
+    }
+
+    #[must_use]
+    #[cfg(test)]
+    pub fn count(&self, kind: ReasonKind) -> usize {
+        self.counts.get(&kind).copied().unwrap_or(0)
+    }
+
+    /// Render one bar per reason kind, most frequent first, e.g.
+    /// `file ████ 42`
+    #[must_use]
+    pub fn render(&self) -> String {
+        let mut rows: Vec<_> = self
+            .counts
+            .iter()
+            .map(|(kind, count)| (kind.to_string(), *count))
+            .collect();
+        rows.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        let label_width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+        let max_count = rows.first().map_or(1, |(_, count)| *count);
+
+        let mut output = String::new();
+        for (label, count) in rows {
+            let bar_len = (count * HISTOGRAM_WIDTH).div_ceil(max_count);
+            let _ = writeln!(
+                output,
+                "{label:<label_width$} {} {count}",
+                "█".repeat(bar_len)
+            );
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use roxmltree::{Document, Node};
+
+    use super::*;
+    use crate::{
+        fingerprint_parser::parse_rebuild_entry,
+        rebuild_graph::{RebuildGraph, tests::cc_cascade_graph},
+    };
+
+    #[test]
+    fn histogram_counts_every_parsed_entry() {
+        let log = [
+            r#"dirty: EnvVarChanged { name: "CC", old_value: Some("gcc"), new_value: None }"#,
+            r#"dirty: EnvVarChanged { name: "CC", old_value: Some("gcc"), new_value: None }"#,
+            r#"dirty: EnvVarChanged { name: "PATH", old_value: None, new_value: Some("/bin") }"#,
+            r#"dirty: UnitDependencyInfoChanged { old_name: "a", old_fingerprint: 1, new_name: "a", new_fingerprint: 2 }"#,
+            r"dirty: TargetConfigurationChanged",
+            r"dirty: TargetConfigurationChanged",
+        ];
+
+        let mut histogram = ReasonHistogram::default();
+        for line in log {
+            histogram.record(&parse_rebuild_entry(line).remove(0).reason);
+        }
+
+        assert_eq!(histogram.count(ReasonKind::EnvVar), 3);
+        assert_eq!(histogram.count(ReasonKind::Dependency), 1);
+        assert_eq!(histogram.count(ReasonKind::TargetConfig), 2);
+        assert_eq!(histogram.count(ReasonKind::SourceFile), 0);
+
+        let rendered = histogram.render();
+        let lines: Vec<_> = rendered.lines().collect();
+        assert_eq!(lines.len(), 3, "One bar per kind: {rendered}");
+        assert!(
+            lines[0].starts_with("env") && lines[0].ends_with(" 3"),
+            "Most frequent kind should come first: {rendered}"
+        );
+    }
+
+    #[test]
+    fn mermaid_links_root_cause_to_affected_package() {
+        let graph = cc_cascade_graph();
+
+        let mermaid = to_mermaid(&graph.root_cause_chains());
+
+        assert!(mermaid.starts_with("flowchart TD"), "Got: {mermaid}");
+        assert!(
+            mermaid.contains("libz_sys_v1_1_23_build_script_build --> rusqlite_v0_31_0"),
+            "Expected an edge from libz-sys to rusqlite, got: {mermaid}"
+        );
+    }
+
+    #[test]
+    fn csv_has_header_and_one_row_per_node() {
+        let mut graph = cc_cascade_graph();
+        graph.add_node(RebuildNode::new(
+            PackageTarget::new("app v0.1.0", Some("app".to_string())),
+            RebuildReason::FeaturesChanged {
+                old: "default".to_string(),
+                new: "default,serde".to_string(),
+            },
+        ));
+
+        let mut buffer = Vec::new();
+        write_csv(graph.nodes(), &mut buffer).unwrap();
+        let csv = String::from_utf8(buffer).unwrap();
+        let lines: Vec<_> = csv.lines().collect();
+
+        assert_eq!(
+            lines,
+            [
+                "package,target,reason_kind,detail,is_root_cause",
+                "libz-sys v1.1.23,build-script-build,env,CC,true",
+                "rusqlite v0.31.0,,dep,libz-sys,false",
+                "app v0.1.0,app,features,\"default -> default,serde\",true",
+            ]
+        );
+    }
+
+    #[test]
+    fn markdown_has_header_and_bullet_per_root_cause() {
+        let mut graph = cc_cascade_graph();
+        graph.add_node(RebuildNode::new(
+            PackageTarget::new("app v0.1.0", None),
+            RebuildReason::TargetConfigurationChanged,
+        ));
+
+        let markdown = to_markdown(&graph.root_cause_chains());
+
+        assert!(
+            markdown.starts_with("## 3 rebuilds from 2 root causes"),
+            "Got: {markdown}"
+        );
+        assert!(
+            markdown.contains("\n- **libz-sys [build-script-build]**: `env:CC ('gcc' -> unset)`")
+        );
+        assert!(markdown.contains("\n- **app**: `target config changed`"));
+        assert!(markdown.contains("    - rusqlite"));
+    }
+
+    #[test]
+    fn key_values_have_one_line_per_root_cause() {
+        let mut graph = cc_cascade_graph();
+        graph.add_node(RebuildNode::new(
+            PackageTarget::new("app v0.1.0", None),
+            RebuildReason::ProfileConfigurationChanged,
+        ));
+
+        let kv = to_key_values(&graph.root_cause_chains());
+
+        assert_eq!(
+            kv.lines().collect::<Vec<_>>(),
+            [
+                "root_cause.package=libz-sys root_cause.target=build-script-build \
+                 root_cause.kind=env root_cause.severity=warn root_cause.reason=\"env:CC ('gcc' \
+                 -> unset)\" affected_count=1",
+                "root_cause.package=app root_cause.target= root_cause.kind=profile \
+                 root_cause.severity=warn root_cause.reason=\"profile changed\" affected_count=0",
+            ]
+        );
+    }
+
+    #[test]
+    fn junit_has_one_testcase_per_root_cause() {
+        let mut graph = cc_cascade_graph();
+        graph.add_node(RebuildNode::new(
+            PackageTarget::new("app v0.1.0", None),
+            RebuildReason::TargetConfigurationChanged,
+        ));
+
+        let xml = to_junit(&graph.root_cause_chains(), |kind| {
+            kind == ReasonKind::EnvVar
+        });
+        let document = Document::parse(&xml).unwrap();
+
+        let suite = document.root_element();
+        assert_eq!(suite.tag_name().name(), "testsuite");
+        assert_eq!(suite.attribute("tests"), Some("2"));
+        assert_eq!(suite.attribute("failures"), Some("1"));
+
+        let cases: Vec<_> = suite.children().filter(Node::is_element).collect();
+        let names: Vec<_> = cases.iter().map(|case| case.attribute("name")).collect();
+        assert_eq!(names, [Some("libz-sys [build-script-build]"), Some("app")]);
+
+        let failure = cases[0].first_element_child().unwrap();
+        assert_eq!(failure.tag_name().name(), "failure");
+        assert!(
+            failure.text().unwrap().contains("'gcc' -> unset"),
+            "Explanation should survive escaping: {failure:?}"
+        );
+        assert!(
+            cases[1].first_element_child().is_none(),
+            "A config change should pass when only env changes fail"
+        );
+    }
+
+    #[test]
+    fn nests_transitive_rebuilds_under_their_cause() {
+        let mut graph = RebuildGraph::new();
+        graph.add_node(RebuildNode::new(
+            PackageTarget::new("a v0.1.0", None),
+            RebuildReason::FileChanged {
+                path: "a/src/lib.rs".to_string(),
+                reference: None,
+            },
+        ));
+        for (package, dependency) in [("b v0.1.0", "a"), ("c v0.1.0", "b")] {
+            graph.add_node(RebuildNode::new(
+                PackageTarget::new(package, None),
+                RebuildReason::UnitDependencyInfoChanged {
+                    name: dependency.to_string(),
+                    old_fingerprint: "1".to_string(),
+                    new_fingerprint: "2".to_string(),
+                    context: None,
+                },
+            ));
+        }
+
+        let trees: Vec<_> = graph
+            .root_cause_chains()
+            .iter()
+            .filter_map(|chain| cascade_tree(&graph, chain))
+            .collect();
+        let json = serde_json::to_value(&trees).unwrap();
+
+        assert_eq!(trees.len(), 1);
+        assert_eq!(json[0]["node"]["package"]["package_id"], "a v0.1.0");
+        assert_eq!(
+            json[0]["cascades"][0]["node"]["package"]["package_id"],
+            "b v0.1.0"
+        );
+        assert_eq!(
+            json[0]["cascades"][0]["cascades"][0]["node"]["package"]["package_id"],
+            "c v0.1.0"
+        );
+        assert_eq!(json[0]["cascades"].as_array().map(Vec::len), Some(1));
+    }
+
+    #[test]
+    fn ndjson_writes_one_line_per_entry() {
+        let log = [
+            r#"    0.010000000s  INFO prepare_target{force=false package_id=cc v1.0.0 target="cc"}: cargo::core::compiler::fingerprint:     dirty: EnvVarChanged { name: "CC", old_value: None, new_value: Some("clang") }"#,
+            r#"    0.020000000s  INFO prepare_target{force=false package_id=app v0.1.0 target="app"}: cargo::core::compiler::fingerprint:     dirty: ProfileConfigurationChanged"#,
+            r"    0.030000000s  INFO cargo::core::compiler::fingerprint: fingerprint error for app",
+        ];
+
+        let mut output = Vec::new();
+        let mut parsed = 0;
+        for entry in log.into_iter().flat_map(parse_rebuild_entry) {
+            write_ndjson_event(&entry, &mut output).unwrap();
+            parsed += 1;
+        }
+
+        let output = String::from_utf8(output).unwrap();
+        let events: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(parsed, 2);
+        assert_eq!(events.len(), parsed);
+        assert_eq!(events[0]["package"], "cc v1.0.0");
+        assert_eq!(events[0]["kind"], "env-var");
+        assert_eq!(events[1]["target"], "app");
+        assert_eq!(events[1]["timestamp"], 0.02);
+    }
+
+    #[test]
+    fn collapses_long_lists_of_rebuilt_packages() {
+        let affected: Vec<_> = (0..40)
+            .map(|i| {
+                RebuildNode::new(
+                    PackageTarget::new(format!("crate-{i} v0.1.0"), None),
+                    RebuildReason::UnitDependencyInfoChanged {
+                        name: "cc".to_string(),
+                        old_fingerprint: "1".to_string(),
+                        new_fingerprint: "2".to_string(),
+                        context: None,
+                    },
+                )
+            })
+            .collect();
+
+        let collapsed = affected_lines(&affected, false);
+        assert_eq!(
+            collapsed,
+            ["Also rebuilt: crate-0, crate-1, crate-2 and 37 more (40 packages)"]
+        );
+
+        let expanded = affected_lines(&affected, true);
+        assert_eq!(expanded.len(), 41);
+        assert_eq!(expanded[40], "  - crate-39");
+    }
+
+    #[test]
+    fn hints_at_moved_target_directory() {
+        let root = Path::new("/home/user/new");
+        let moved = |package: &str| {
+            RebuildNode::new(
+                PackageTarget::new(package, None),
+                RebuildReason::FileChanged {
+                    path: format!("/home/user/old/{package}/src/lib.rs"),
+                    reference: Some(format!(
+                        "/home/user/new/target/debug/.fingerprint/{package}-1/dep-lib"
+                    )),
+                },
+            )
+        };
+        let in_place = RebuildNode::new(
+            PackageTarget::new("app", None),
+            RebuildReason::FileChanged {
+                path: "/home/user/new/src/main.rs".to_string(),
+                reference: Some(
+                    "/home/user/new/target/debug/.fingerprint/app-1/dep-bin".to_string(),
+                ),
+            },
+        );
+
+        assert_eq!(
+            relocation_hint(&[moved("lib-a"), in_place.clone()], Some(root)),
+            None
+        );
+        assert_eq!(
+            relocation_hint(&[moved("lib-a"), moved("lib-b"), in_place], Some(root)),
+            Some(RELOCATION_HINT)
+        );
+
+        let dep_info = |package: &str, project: &str| {
+            RebuildNode::new(
+                PackageTarget::new(package, None),
+                RebuildReason::DepInfoOutputChanged {
+                    old: format!("/home/user/old/target/debug/deps/{package}-1.d"),
+                    new: format!("{project}/target/debug/deps/{package}-1.d"),
+                },
+            )
+        };
+        assert_eq!(
+            relocation_hint(
+                &[
+                    dep_info("lib-a", "/home/user/new"),
+                    dep_info("lib-b", "/home/user/new")
+                ],
+                None
+            ),
+            Some(RELOCATION_HINT)
+        );
+        assert_eq!(
+            relocation_hint(
+                &[
+                    dep_info("lib-a", "/home/user/old"),
+                    dep_info("lib-b", "/home/user/old")
+                ],
+                None
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn out_of_tree_target_directory_is_not_a_move() {
+        let edit = |file: &str| {
+            RebuildNode::new(
+                PackageTarget::new("app", None),
+                RebuildReason::FileChanged {
+                    path: format!("/home/u/project/src/{file}"),
+                    reference: Some(
+                        "/home/u/.cargo/target/debug/.fingerprint/app-1/dep-bin".to_string(),
+                    ),
+                },
+            )
+        };
+
+        assert_eq!(
+            relocation_hint(
+                &[edit("main.rs"), edit("lib.rs")],
+                Some(Path::new("/home/u/project"))
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn nodes_json_lists_every_unique_node() {
+        let mut graph = cc_cascade_graph();
+        graph.add_node(graph.nodes()[1].clone());
+
+        let json: serde_json::Value = serde_json::from_str(
+            &nodes_to_json(&graph.nodes().iter().collect::<Vec<_>>(), JsonStyle::Pretty).unwrap(),
+        )
+        .unwrap();
+
+        let nodes = json.as_array().unwrap();
+        assert_eq!(nodes.len(), graph.len());
+        for node in nodes {
+            assert!(
+                node.get("package").is_some() && node.get("reason").is_some(),
+                "Each node needs a package and a reason: {node}"
+            );
+        }
+        assert_eq!(
+            nodes[1]["reason"]["UnitDependencyInfoChanged"]["new_fingerprint"],
+            "2"
+        );
+    }
+
+    #[test]
+    fn shows_changed_files_relative_to_the_project_root() {
+        let reason = RebuildReason::FileChanged {
+            path: "/home/dev/project/src/main.rs".to_string(),
+            reference: Some("/tmp/elsewhere/dep-bin-app".to_string()),
+        };
+        let style = TextStyle {
+            project_root: Some(Path::new("/home/dev/project")),
+            ..TextStyle::default()
+        };
+
+        let explanation = style.explanation(&reason);
+        assert!(
+            explanation.contains("Source file changed: src/main.rs"),
+            "{explanation}"
+        );
+        assert!(
+            explanation.contains("Compared against: /tmp/elsewhere/dep-bin-app"),
+            "{explanation}"
+        );
+
+        let absolute = TextStyle::default().explanation(&reason);
+        assert!(absolute.contains("Source file changed: /home/dev/project/src/main.rs"));
+    }
+
+    #[test]
+    fn groups_root_causes_sharing_a_changed_file() {
+        let stale = |package: &str| RootCauseChain {
+            root_cause: RebuildNode::new(
+                PackageTarget::new(format!("{package} v0.1.0"), Some(package.to_string())),
+                RebuildReason::FileChanged {
+                    path: "/src/shared/main.rs".to_string(),
+                    reference: Some(format!("/target/debug/.fingerprint/{package}/dep-lib")),
+                },
+            ),
+            affected_packages: Vec::new(),
+        };
+        let profile = RootCauseChain {
+            root_cause: RebuildNode::new(
+                PackageTarget::new("other v0.1.0", None),
+                RebuildReason::ProfileConfigurationChanged,
+            ),
+            affected_packages: Vec::new(),
+        };
+        let chains = [stale("a"), profile, stale("b"), stale("c")];
+        let roots: Vec<_> = chains.iter().collect();
+
+        let groups = group_by_changed_file(&roots);
+        assert_eq!(groups.len(), 2);
+        let packages: Vec<_> = groups[0]
+            .iter()
+            .map(|chain| chain.root_cause.package.name())
+            .collect();
+        assert_eq!(packages, ["a", "b", "c"]);
+        assert_eq!(groups[1].len(), 1);
+    }
+
+    #[test]
+    fn lists_every_rebuilt_target_under_its_package() {
+        let mut graph = RebuildGraph::new();
+        graph.add_node(RebuildNode::new(
+            PackageTarget::new("lib-a v0.1.0", Some("lib_a".to_string())),
+            RebuildReason::FileChanged {
+                path: "src/lib.rs".to_string(),
+                reference: None,
+            },
+        ));
+        graph.add_node(RebuildNode::new(
+            PackageTarget::new("app v0.1.0", Some("app".to_string())),
+            RebuildReason::ProfileConfigurationChanged,
+        ));
+        graph.add_node(RebuildNode::new(
+            PackageTarget::new("lib-a v0.1.0", Some("integration".to_string())),
+            RebuildReason::FileChanged {
+                path: "tests/integration.rs".to_string(),
+                reference: None,
+            },
+        ));
+
+        let groups = targets_by_package(graph.nodes());
+
+        assert_eq!(groups.len(), 1, "Only lib-a rebuilt several targets");
+        let (package, targets) = &groups[0];
+        assert_eq!(*package, "lib-a");
+        let targets: Vec<_> = targets
+            .iter()
+            .map(|node| (node.package.target.as_deref(), node.reason.to_string()))
+            .collect();
+        assert_eq!(
+            targets,
+            [
+                (Some("lib_a"), "file:src/lib.rs".to_string()),
+                (Some("integration"), "file:tests/integration.rs".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn groups_nodes_by_reason_kind() {
+        let mut graph = RebuildGraph::new();
+        graph.add_node(RebuildNode::new(
+            PackageTarget::new("app v0.1.0", None),
+            RebuildReason::FileChanged {
+                path: "src/main.rs".to_string(),
+                reference: None,
+            },
+        ));
+        for package in ["libz-sys v1.1.23", "cc v1.0.0"] {
+            graph.add_node(RebuildNode::new(
+                PackageTarget::new(package, None),
+                RebuildReason::EnvVarChanged {
+                    name: "CC".to_string(),
+                    old_value: None,
+                    new_value: Some("clang".to_string()),
+                },
+            ));
+        }
+
+        let groups = group_by_reason_kind(graph.nodes());
+
+        let counts: Vec<_> = groups
+            .iter()
+            .map(|(kind, nodes)| (*kind, nodes.len()))
+            .collect();
+        assert_eq!(
+            counts,
+            [(ReasonKind::EnvVar, 2), (ReasonKind::SourceFile, 1)]
+        );
+    }
+
+    #[test]
+    fn bottom_line_names_the_most_impactful_root_cause() {
+        let chain = |package: &str, reason: RebuildReason, affected: usize| RootCauseChain {
+            root_cause: RebuildNode::new(
+                PackageTarget::new(format!("{package} v0.1.0"), None),
+                reason,
+            ),
+            affected_packages: (0..affected)
+                .map(|i| {
+                    RebuildNode::new(
+                        PackageTarget::new(format!("dependent-{i} v0.1.0"), None),
+                        RebuildReason::ProfileConfigurationChanged,
+                    )
+                })
+                .collect(),
+        };
+        let chains = [
+            chain("app", RebuildReason::Unknown("Something".to_string()), 2),
+            chain("lib-a", RebuildReason::ProfileConfigurationChanged, 2),
+            chain("lib-b", RebuildReason::TargetConfigurationChanged, 0),
+        ];
+
+        assert_eq!(
+            bottom_line(&chains),
+            "Bottom line: Profile configuration changed in lib-a caused 3 rebuilds."
+        );
+        assert_eq!(
+            bottom_line(&[]),
+            "Bottom line: clean build, nothing was rebuilt."
+        );
+    }
+}
@@ -0,0 +1,149 @@
+//! Interactive browser for root causes
+//!
+//! Lists the root causes on the left and shows the explanation and the
+//! rebuilt packages of the selected one on the right.
+
+use std::io::{self, IsTerminal};
+
+use ratatui::{
+    DefaultTerminal, Frame,
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, List, ListState, Paragraph, Wrap},
+};
+
+use crate::{AnalyzerError, rebuild_graph::RootCauseChain, rebuild_reason::IconStyle};
+
+/// Root causes being browsed and the selected one
+#[derive(Debug)]
+pub struct App {
+    chains: Vec<RootCauseChain>,
+    list: ListState,
+}
+
+impl App {
+    #[must_use]
+    pub fn new(chains: Vec<RootCauseChain>) -> Self {
+        let list = ListState::default().with_selected((!chains.is_empty()).then_some(0));
+        Self { chains, list }
+    }
+
+    fn selected(&self) -> Option<&RootCauseChain> {
+        self.list.selected().and_then(|idx| self.chains.get(idx))
+    }
+
+    fn run(mut self, terminal: &mut DefaultTerminal) -> Result<(), AnalyzerError> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Down | KeyCode::Char('j') => self.list.select_next(),
+                KeyCode::Up | KeyCode::Char('k') => self.list.select_previous(),
+                _ => {}
+            }
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame<'_>) {
+        let [main, help] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+        let [left, right] =
+            Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)])
+                .areas(main);
+
+        let items = self.chains.iter().map(|chain| {
+            format!(
+                "{} {} ({})",
+                chain.root_cause.package,
+                chain.root_cause.reason,
+                chain.total_rebuilds()
+            )
+        });
+        let list = List::new(items)
+            .block(Block::bordered().title("Root causes"))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, left, &mut self.list);
+
+        let details = self.selected().map_or_else(
+            || vec![Line::from("No rebuild triggers detected.")],
+            detail_lines,
+        );
+        let paragraph = Paragraph::new(details)
+            .block(Block::bordered().title("Details"))
+            .wrap(Wrap { trim: false });
+        frame.render_widget(paragraph, right);
+
+        frame.render_widget(Line::from("up/down or j/k: select  q: quit"), help);
+    }
+}
+
+// Explanation of the root cause followed by the packages it rebuilt
+fn detail_lines(chain: &RootCauseChain) -> Vec<Line<'static>> {
+    let mut lines: Vec<_> = chain
+        .root_cause
+        .reason
+        .explanation_with(IconStyle::Ascii)
+        .lines()
+        .map(|line| Line::from(line.to_string()))
+        .collect();
+    if !chain.affected_packages.is_empty() {
+        lines.push(Line::from(""));
+        lines.push(Line::from("Also rebuilt:"));
+        lines.extend(
+            chain
+                .affected_packages
+                .iter()
+                .map(|node| Line::from(format!("  - {}", node.package))),
+        );
+    }
+    lines
+}
+
+/// Browse `chains` in the terminal until `q` or Esc is pressed
+///
+/// # Errors
+/// Returns [`AnalyzerError::NotATerminal`] when stdin or stdout is not a
+/// terminal, and an IO error when the terminal cannot be set up
+pub fn run(chains: Vec<RootCauseChain>) -> Result<(), AnalyzerError> {
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return Err(AnalyzerError::NotATerminal);
+    }
+    let mut terminal = ratatui::try_init()?;
+    let result = App::new(chains).run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        analyze_log_lines,
+        rebuild_graph::{PackageTarget, RebuildNode, tests::cc_cascade_graph},
+        rebuild_reason::RebuildReason,
+    };
+
+    #[test]
+    fn app_lists_every_root_cause() {
+        let mut graph = cc_cascade_graph();
+        graph.add_node(RebuildNode::new(
+            PackageTarget::new("app v0.1.0", None),
+            RebuildReason::ProfileConfigurationChanged,
+        ));
+
+        let app = App::new(graph.root_cause_chains());
+        assert_eq!(app.chains.len(), 2);
+        assert!(app.selected().is_some());
+
+        let empty = App::new(analyze_log_lines(Vec::new()).root_cause_chains());
+        assert!(empty.chains.is_empty() && empty.selected().is_none());
+    }
+}
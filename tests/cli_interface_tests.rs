@@ -1,8 +1,133 @@
-use std::{fs, process::Command};
+use std::{
+    fs,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
 
 use assert_cmd::{cargo, prelude::*};
 use tempfile::TempDir;
 
+/// Write `lines` to a log in a new temporary directory, which has to outlive
+/// every read of the log
+fn write_log(lines: &[impl AsRef<str>]) -> (TempDir, PathBuf) {
+    let logs = TempDir::new().unwrap();
+    let log = logs.path().join("build.log");
+    let lines: Vec<_> = lines.iter().map(AsRef::as_ref).collect();
+    fs::write(&log, lines.join("\n")).unwrap();
+    (logs, log)
+}
+
+fn create_binary_project() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+
+    fs::write(
+        temp_dir.path().join("Cargo.toml"),
+        r#"
+[package]
+name = "test-project"
+version = "0.1.0"
+edition = "2021"
+"#,
+    )
+    .unwrap();
+
+    let src_dir = temp_dir.path().join("src");
+    fs::create_dir(&src_dir).unwrap();
+    fs::write(src_dir.join("main.rs"), "fn main() {}").unwrap();
+
+    temp_dir
+}
+
+/// Workspace where `app` depends on `lib-b`, which depends on `lib-a`
+fn create_workspace_with_dependencies() -> TempDir {
+    create_workspace(&[
+        (
+            "lib-a",
+            "",
+            "src/lib.rs",
+            "pub fn greet() -> &'static str { \"hi\" }",
+        ),
+        (
+            "lib-b",
+            "lib-a = { path = \"../lib-a\" }",
+            "src/lib.rs",
+            "pub fn message() -> String { lib_a::greet().to_string() }",
+        ),
+        (
+            "app",
+            "lib-b = { path = \"../lib-b\" }",
+            "src/main.rs",
+            "fn main() { println!(\"{}\", lib_b::message()); }",
+        ),
+    ])
+}
+
+/// Workspace from `(name, dependencies, source file, source)` members
+fn create_workspace(members: &[(&str, &str, &str, &str)]) -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+
+    let names: Vec<_> = members
+        .iter()
+        .map(|(name, ..)| format!("\"{name}\""))
+        .collect();
+    fs::write(
+        temp_dir.path().join("Cargo.toml"),
+        format!(
+            "[workspace]\nmembers = [{}]\nresolver = \"2\"\n",
+            names.join(", ")
+        ),
+    )
+    .unwrap();
+
+    for &(name, dependencies, source_file, source) in members {
+        let member_dir = temp_dir.path().join(name);
+        fs::create_dir_all(member_dir.join("src")).unwrap();
+        fs::write(
+            member_dir.join("Cargo.toml"),
+            format!(
+                r#"
+[package]
+name = "{name}"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+{dependencies}
+"#
+            ),
+        )
+        .unwrap();
+        fs::write(member_dir.join(source_file), source).unwrap();
+    }
+
+    temp_dir
+}
+
+/// Check the workspace once, then edit both `lib-a` and `app`
+fn create_workspace_with_two_edits() -> TempDir {
+    let workspace = create_workspace_with_dependencies();
+
+    Command::new("cargo")
+        .arg("check")
+        .current_dir(workspace.path())
+        .assert()
+        .success();
+
+    fs::write(
+        workspace.path().join("lib-a/src/lib.rs"),
+        "pub fn greet() -> &'static str { \"hello\" }",
+    )
+    .unwrap();
+    fs::write(
+        workspace.path().join("app/src/main.rs"),
+        "fn main() { println!(\"> {}\", lib_b::message()); }",
+    )
+    .unwrap();
+
+    workspace
+}
+
 #[test]
 fn cli_reports_error_for_invalid_project_path() {
     let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
@@ -50,3 +175,1135 @@ edition = "2021"
         );
     }
 }
+
+#[test]
+fn cli_watch_once_reanalyzes_after_one_change() {
+    let project = create_binary_project();
+
+    let mut child = Command::new(cargo::cargo_bin!("cargo-frequent"))
+        .arg("--path")
+        .arg(project.path())
+        .arg("--watch-once")
+        .arg("--watch-timeout")
+        .arg("60")
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut output = String::new();
+    while !output.contains("Watching") {
+        assert!(
+            stdout.read_line(&mut output).unwrap() > 0,
+            "Process exited before watching, got: {output}"
+        );
+    }
+
+    fs::write(
+        project.path().join("src/main.rs"),
+        "fn main() { println!(\"changed\"); }",
+    )
+    .unwrap();
+
+    let mut rest = String::new();
+    while stdout.read_line(&mut rest).unwrap() > 0 {}
+    assert!(
+        child.wait().unwrap().success(),
+        "Process should exit cleanly"
+    );
+
+    let added: Vec<_> = rest
+        .lines()
+        .filter(|line| line.starts_with("  + "))
+        .collect();
+    assert!(
+        rest.contains("Changes since the first analysis:")
+            && added.iter().any(|line| line.contains("main.rs"))
+            && !rest.contains("Running: cargo check"),
+        "Expected only the delta with main.rs added, got: {rest}"
+    );
+}
+
+#[test]
+fn cli_watch_once_exits_when_nothing_changes() {
+    let project = create_binary_project();
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--path")
+        .arg(project.path())
+        .arg("--watch-once")
+        .arg("--watch-timeout")
+        .arg("1");
+
+    let output = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    assert!(
+        stdout.contains("No changes within 1s"),
+        "Expected the timeout message, got: {stdout}"
+    );
+}
+
+#[test]
+fn cli_saves_raw_cargo_log() {
+    let project = create_binary_project();
+    let log_path = project.path().join("cargo.log");
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--path")
+        .arg(project.path())
+        .arg("--save-log")
+        .arg(&log_path);
+    cmd.assert().success();
+
+    let log = fs::read_to_string(&log_path).unwrap();
+    assert!(
+        log.lines().any(|line| line.contains("fingerprint")),
+        "Expected fingerprint lines in saved log, got: {log}"
+    );
+}
+
+#[test]
+fn cli_package_filter_limits_root_causes() {
+    let workspace = create_workspace_with_two_edits();
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--path")
+        .arg(workspace.path())
+        .arg("--package")
+        .arg("lib-a");
+
+    let output = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    assert!(
+        stdout.contains("1 root cause:") && stdout.contains("  lib-a [lib_a]"),
+        "Expected only the lib-a root cause, got: {stdout}"
+    );
+    assert!(
+        !stdout.contains("  app [app]"),
+        "Expected app to be filtered out, got: {stdout}"
+    );
+}
+
+#[test]
+fn cli_exclude_hides_packages() {
+    let workspace = create_workspace_with_two_edits();
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--path")
+        .arg(workspace.path())
+        .arg("--exclude")
+        .arg("app");
+
+    let output = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    assert!(
+        stdout.contains("  lib-a [lib_a]"),
+        "Expected lib-a to remain, got: {stdout}"
+    );
+    assert!(
+        !stdout.contains("  app [app]"),
+        "Expected app to be excluded, got: {stdout}"
+    );
+}
+
+#[test]
+fn cli_color_never_prints_no_escape_sequences() {
+    let workspace = create_workspace_with_two_edits();
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--path")
+        .arg(workspace.path())
+        .arg("--color")
+        .arg("never");
+
+    let output = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    assert!(
+        stdout.contains("lib-a") && !stdout.contains('\x1b'),
+        "Expected uncolored root causes, got: {stdout:?}"
+    );
+}
+
+#[test]
+fn cli_top_limits_root_causes() {
+    let workspace = create_workspace(&[
+        ("one", "", "src/lib.rs", "pub const ONE: u8 = 1;"),
+        ("two", "", "src/lib.rs", "pub const TWO: u8 = 2;"),
+    ]);
+    Command::new("cargo")
+        .arg("check")
+        .current_dir(workspace.path())
+        .assert()
+        .success();
+    for name in ["one", "two"] {
+        fs::write(
+            workspace.path().join(name).join("src/lib.rs"),
+            "pub const CHANGED: bool = true;",
+        )
+        .unwrap();
+    }
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--path")
+        .arg(workspace.path())
+        .arg("--top")
+        .arg("1")
+        .arg("--color")
+        .arg("never");
+
+    let output = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    let root_lines = stdout
+        .lines()
+        .filter(|line| line.starts_with("  ") && !line.starts_with("   "))
+        .count();
+    assert_eq!(
+        root_lines, 2,
+        "Expected one root cause and a summary line, got: {stdout}"
+    );
+    assert!(
+        stdout.contains("more)"),
+        "Expected a line counting the hidden root causes, got: {stdout}"
+    );
+}
+
+#[test]
+fn cli_frequent_ranks_rebuild_reasons() {
+    let workspace = create_workspace_with_two_edits();
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("frequent")
+        .arg("--path")
+        .arg(workspace.path())
+        .arg("--format")
+        .arg("frequency");
+
+    let output = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    assert!(
+        stdout
+            .lines()
+            .any(|line| line.contains("lib.rs") && line.ends_with(": 3 units rebuilt")),
+        "Expected the edited file credited with the crates it rebuilt, got: {stdout}"
+    );
+}
+
+#[test]
+fn cli_snapshot_saves_every_rebuilt_unit() {
+    let workspace = create_workspace_with_two_edits();
+    let snapshot = workspace.path().join("snapshot.json");
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--path")
+        .arg(workspace.path())
+        .arg("--snapshot")
+        .arg(&snapshot);
+    cmd.assert().success();
+
+    let analysis: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&snapshot).unwrap()).unwrap();
+    let nodes = analysis["nodes"].as_array().unwrap();
+    assert!(!nodes.is_empty(), "Expected rebuilt units in {analysis}");
+    assert_eq!(
+        analysis["unique_rebuilds"].as_u64(),
+        u64::try_from(nodes.len()).ok(),
+        "Node count should match unique_rebuilds in {analysis}"
+    );
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--path")
+        .arg(workspace.path())
+        .arg("--baseline")
+        .arg(&snapshot);
+    let output = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    assert!(
+        stdout.contains("Compared with baseline:"),
+        "Expected the snapshot to load as a baseline, got: {stdout}"
+    );
+}
+
+#[test]
+fn cli_by_member_prints_a_section_per_rebuilt_member() {
+    let workspace = create_workspace_with_dependencies();
+    Command::new("cargo")
+        .arg("check")
+        .current_dir(workspace.path())
+        .assert()
+        .success();
+    fs::write(
+        workspace.path().join("lib-a/src/lib.rs"),
+        "pub fn greet() -> &'static str { \"hello\" }",
+    )
+    .unwrap();
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--path").arg(workspace.path()).arg("--by-member");
+
+    let output = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    for member in ["lib-a", "lib-b", "app"] {
+        assert!(
+            stdout.contains(&format!("== {member} ==")),
+            "Expected a section for {member}, got: {stdout}"
+        );
+    }
+}
+
+#[test]
+fn cli_quiet_prints_only_summary_counts() {
+    let workspace = create_workspace_with_two_edits();
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--path").arg(workspace.path()).arg("--quiet");
+
+    let output = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    assert!(
+        stdout.contains("files: 1") && stdout.contains("total: "),
+        "Expected summary counts, got: {stdout}"
+    );
+    assert!(
+        stdout.is_ascii() && !stdout.contains("Suggestions"),
+        "Expected no explanations, got: {stdout}"
+    );
+}
+
+#[test]
+fn cli_rejects_quiet_with_verbose() {
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--quiet").arg("--verbose");
+
+    cmd.assert().failure();
+}
+
+#[test]
+fn cli_passes_custom_cargo_log_spec() {
+    let workspace = create_workspace_with_two_edits();
+    let log_path = workspace.path().join("cargo.log");
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--path")
+        .arg(workspace.path())
+        .arg("--cargo-log")
+        .arg("cargo::core::compiler::fingerprint=debug")
+        .arg("--save-log")
+        .arg(&log_path);
+
+    let output = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    let log = fs::read_to_string(&log_path).unwrap();
+    assert!(
+        log.contains(" DEBUG "),
+        "Expected debug-level fingerprint lines, got: {log}"
+    );
+    assert!(
+        stdout.contains("lib-a [lib_a]"),
+        "Expected root causes to still be found, got: {stdout}"
+    );
+}
+
+#[test]
+fn cli_timeout_stops_a_slow_build() {
+    let project = create_binary_project();
+    fs::write(
+        project.path().join("build.rs"),
+        "fn main() { std::thread::sleep(std::time::Duration::from_secs(30)); }",
+    )
+    .unwrap();
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--path")
+        .arg(project.path())
+        .arg("--timeout")
+        .arg("1");
+
+    let output = cmd.assert().failure();
+    let stderr = String::from_utf8_lossy(&output.get_output().stderr);
+    assert!(
+        stderr.contains("cargo did not finish within 1s"),
+        "Expected a timeout error, got: {stderr}"
+    );
+}
+
+#[test]
+fn cli_watch_reanalyzes_until_killed() {
+    let project = create_binary_project();
+
+    let mut child = Command::new(cargo::cargo_bin!("cargo-frequent"))
+        .arg("--path")
+        .arg(project.path())
+        .arg("--watch")
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut output = String::new();
+    while !output.contains("Watching") {
+        assert!(
+            stdout.read_line(&mut output).unwrap() > 0,
+            "Process exited before watching, got: {output}"
+        );
+    }
+
+    fs::write(
+        project.path().join("src/main.rs"),
+        "fn main() { println!(\"changed\"); }",
+    )
+    .unwrap();
+
+    let mut rest = String::new();
+    while !rest.contains("Watching") {
+        assert!(
+            stdout.read_line(&mut rest).unwrap() > 0,
+            "Process exited instead of watching again, got: {rest}"
+        );
+    }
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    assert!(
+        rest.contains("Running: cargo check") && rest.contains("main.rs"),
+        "Expected a second analysis reporting main.rs, got: {rest}"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn cli_runs_the_given_cargo_binary() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let project = create_binary_project();
+    let marker = project.path().join("wrapper-invoked");
+    let wrapper = project.path().join("cargo-wrapper.sh");
+    fs::write(
+        &wrapper,
+        format!(
+            "#!/bin/sh\ntouch '{}'\nexec cargo \"$@\"\n",
+            marker.display()
+        ),
+    )
+    .unwrap();
+    fs::set_permissions(&wrapper, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--path")
+        .arg(project.path())
+        .arg("--cargo-bin")
+        .arg(&wrapper);
+    cmd.assert().success();
+
+    assert!(marker.exists(), "The wrapper script was not invoked");
+}
+
+#[test]
+fn cli_dry_run_prints_command_without_running_it() {
+    let project = create_binary_project();
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--path")
+        .arg(project.path())
+        .arg("--dry-run")
+        .arg("--command")
+        .arg("build --release");
+
+    let output = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    assert!(
+        stdout.contains("cargo build --release")
+            && stdout.contains("CARGO_LOG=cargo::core::compiler::fingerprint=info"),
+        "Expected the command line, got: {stdout}"
+    );
+    assert!(
+        !stdout.contains("Running:") && !project.path().join("target").exists(),
+        "Cargo should not run, got: {stdout}"
+    );
+}
+
+#[test]
+fn cli_analyzes_clippy_and_doc_runs() {
+    for command in ["clippy", "doc"] {
+        let project = create_binary_project();
+        Command::new("cargo")
+            .arg(command)
+            .current_dir(project.path())
+            .assert()
+            .success();
+        fs::write(
+            project.path().join("src/main.rs"),
+            "fn main() { println!(\"changed\"); }",
+        )
+        .unwrap();
+
+        let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+        cmd.arg("--path")
+            .arg(project.path())
+            .arg("--command")
+            .arg(command);
+
+        let output = cmd.assert().success();
+        let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+        assert!(
+            stdout.contains(&format!("Running: cargo {command}")) && stdout.contains("main.rs"),
+            "Expected `cargo {command}` to report the edited main.rs, got: {stdout}"
+        );
+    }
+}
+
+#[test]
+fn cli_sums_rebuilds_across_input_logs() {
+    let edit = r#"    0.010000000s  INFO prepare_target{force=false package_id=lib-a v0.1.0 target="lib_a"}: cargo::core::compiler::fingerprint:     dirty: ProfileConfigurationChanged"#;
+    let other = r#"    0.020000000s  INFO prepare_target{force=false package_id=app v0.1.0 target="app"}: cargo::core::compiler::fingerprint:     dirty: ProfileConfigurationChanged"#;
+    let (_monday_logs, monday) = write_log(&[edit, other]);
+    let (_tuesday_logs, tuesday) = write_log(&[edit]);
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--input")
+        .arg(&monday)
+        .arg("--input")
+        .arg(&tuesday)
+        .arg("--format")
+        .arg("frequency");
+
+    let output = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    assert!(
+        stdout.contains("profile changed: 3 units rebuilt") && stdout.contains("lib-a: 2 rebuilds"),
+        "Expected counts summed over both logs, got: {stdout}"
+    );
+}
+
+#[test]
+fn cli_warns_when_cargo_printed_no_fingerprints() {
+    let (_logs, log) = write_log(&[
+        "   Compiling lib-a v0.1.0",
+        "    Finished `dev` profile [unoptimized + debuginfo] target(s) in 0.10s",
+    ]);
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--input").arg(&log);
+
+    let output = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    let stderr = String::from_utf8_lossy(&output.get_output().stderr);
+    assert!(
+        stderr.contains("no fingerprint output"),
+        "Expected a missing fingerprint warning, got: {stderr}"
+    );
+    assert!(
+        !stdout.contains("No rebuild triggers detected"),
+        "A silent log should not be reported as a clean build, got: {stdout}"
+    );
+}
+
+#[test]
+fn cli_notes_packages_recompiled_without_explanation() {
+    let dirty = r#"    0.010000000s  INFO prepare_target{force=false package_id=lib-a v0.1.0 target="lib_a"}: cargo::core::compiler::fingerprint:     dirty: ProfileConfigurationChanged"#;
+    let (_logs, log) = write_log(&[
+        dirty,
+        "   Compiling lib-a v0.1.0 (/src/lib-a)",
+        "   Compiling app v0.1.0 (/src/app)",
+    ]);
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--input").arg(&log);
+
+    let output = cmd.assert().success();
+    let stderr = String::from_utf8_lossy(&output.get_output().stderr);
+    assert!(
+        stderr.contains("app v0.1.0 recompiled without explanation"),
+        "Expected a notice for the unexplained recompile, got: {stderr}"
+    );
+    assert!(
+        !stderr.contains("lib-a v0.1.0 recompiled without explanation"),
+        "lib-a has a rebuild reason, got: {stderr}"
+    );
+}
+
+#[test]
+fn cli_no_default_log_override_leaves_rust_log_unset() {
+    let project = create_binary_project();
+    let dry_run = |extra: &[&str]| {
+        let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+        cmd.arg("--path")
+            .arg(project.path())
+            .arg("--dry-run")
+            .args(extra);
+        let output = cmd.assert().success();
+        String::from_utf8_lossy(&output.get_output().stdout).into_owned()
+    };
+
+    let default = dry_run(&[]);
+    assert!(
+        default.contains("RUST_LOG=debug"),
+        "Expected RUST_LOG=debug by default, got: {default}"
+    );
+
+    let overridden = dry_run(&["--no-default-log-override"]);
+    assert!(
+        !overridden.contains("RUST_LOG")
+            && overridden.contains("CARGO_LOG=cargo::core::compiler::fingerprint=info"),
+        "Expected only CARGO_LOG to be set, got: {overridden}"
+    );
+}
+
+#[test]
+fn cli_prints_frequency_report_as_json() {
+    let dirty = |package: &str, reason: &str| {
+        format!(
+            "    0.010000000s  INFO prepare_target{{force=false package_id={package} v0.1.0 \
+             target=\"{package}\"}}: cargo::core::compiler::fingerprint:     dirty: {reason}"
+        )
+    };
+    let lines = [
+        dirty("lib-a", "ProfileConfigurationChanged"),
+        dirty(
+            "lib-a",
+            r#"EnvVarChanged { name: "CC", old_value: None, new_value: Some("clang") }"#,
+        ),
+        dirty(
+            "lib-b",
+            r#"EnvVarChanged { name: "CC", old_value: None, new_value: Some("clang") }"#,
+        ),
+        dirty(
+            "app",
+            r#"EnvVarChanged { name: "CC", old_value: None, new_value: Some("clang") }"#,
+        ),
+    ];
+    let (_logs, log) = write_log(&lines);
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--input")
+        .arg(&log)
+        .arg("--format")
+        .arg("frequency")
+        .arg("--json");
+
+    let output = cmd.assert().success();
+    let report: serde_json::Value = serde_json::from_slice(&output.get_output().stdout).unwrap();
+    let entries = report["entries"].as_array().unwrap();
+    let counts: Vec<_> = entries
+        .iter()
+        .map(|entry| entry["count"].as_u64().unwrap())
+        .collect();
+    assert_eq!(counts, [3, 1]);
+    assert_eq!(entries[0]["reason_kind"], "env-var");
+    assert_eq!(entries[0]["detail"], "CC");
+}
+
+#[test]
+fn cli_bottom_line_names_the_edited_library() {
+    let workspace = create_workspace_with_dependencies();
+    Command::new("cargo")
+        .arg("check")
+        .current_dir(workspace.path())
+        .assert()
+        .success();
+    fs::write(
+        workspace.path().join("lib-a/src/lib.rs"),
+        "pub fn greet() -> &'static str { \"hello\" }",
+    )
+    .unwrap();
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--path").arg(workspace.path());
+
+    let output = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    let bottom_line = stdout.lines().last().unwrap_or_default();
+    assert!(
+        bottom_line.starts_with("Bottom line: Source file changed")
+            && bottom_line.contains(" in lib-a "),
+        "Expected the bottom line to name lib-a, got: {stdout}"
+    );
+}
+
+#[test]
+fn cli_match_parses_lines_with_custom_markers() {
+    let (_logs, log) = write_log(&[
+        r#"    0.010000000s  INFO prepare_target{force=false package_id=app v0.1.0 target="app"}: forked_cargo::freshness:     dirty: ProfileConfigurationChanged"#,
+    ]);
+
+    let analyze = |extra: &[&str]| {
+        let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+        cmd.arg("--input").arg(&log).args(extra);
+        let output = cmd.assert().success();
+        String::from_utf8_lossy(&output.get_output().stdout).into_owned()
+    };
+
+    let default = analyze(&[]);
+    assert!(
+        default.contains("No rebuild triggers detected"),
+        "Expected the fork's line to be skipped by default, got: {default}"
+    );
+
+    let matched = analyze(&["--match", "forked_cargo::freshness"]);
+    assert!(
+        matched.contains("app [app] profile changed"),
+        "Expected the fork's line to be parsed, got: {matched}"
+    );
+}
+
+#[test]
+fn cli_explains_a_reason_kind_without_running_cargo() {
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--explain").arg("rustflags");
+
+    let output = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    assert!(
+        stdout.contains("RUSTFLAGS changed")
+            && stdout.contains("build.rustflags in .cargo/config.toml"),
+        "Expected the RUSTFLAGS guidance, got: {stdout}"
+    );
+    assert!(
+        !stdout.contains("Running:"),
+        "Cargo should not run, got: {stdout}"
+    );
+}
+
+#[test]
+fn cli_lists_units_rebuilt_according_to_json_messages() {
+    let (_logs, messages) = write_log(&[
+        r#"{"reason":"compiler-artifact","package_id":"path+file:///src/lib-a#0.1.0","target":{"kind":["lib"],"name":"lib_a"},"fresh":true}"#,
+        r#"{"reason":"compiler-artifact","package_id":"path+file:///src/app#0.1.0","target":{"kind":["bin"],"name":"app"},"fresh":false}"#,
+        r#"{"reason":"build-finished","success":true}"#,
+    ]);
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--message-format-json")
+        .arg("--input")
+        .arg(&messages);
+
+    let output = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    assert!(
+        stdout.contains("1 unit rebuilt:")
+            && stdout.contains("  app [app]")
+            && !stdout.contains("lib-a"),
+        "Expected only the non-fresh unit, got: {stdout}"
+    );
+}
+
+#[test]
+fn cli_since_drops_triggers_logged_before_the_cutoff() {
+    let (_logs, log) = write_log(&[
+        r#"    0.500000000s  INFO prepare_target{force=false package_id=lib-a v0.1.0 target="lib_a"}: cargo::core::compiler::fingerprint:     dirty: ProfileConfigurationChanged"#,
+        r#"    1.500000000s  INFO prepare_target{force=false package_id=app v0.1.0 target="app"}: cargo::core::compiler::fingerprint:     dirty: TargetConfigurationChanged"#,
+    ]);
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--input").arg(&log).arg("--since").arg("1.0");
+
+    let output = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    assert!(
+        stdout.contains("app") && !stdout.contains("lib-a"),
+        "Expected only the trigger after 1.0s, got: {stdout}"
+    );
+}
+
+#[test]
+fn cli_output_is_identical_for_the_same_log() {
+    let (_logs, log) = write_log(&[
+        r#"    0.010000000s  INFO prepare_target{force=false package_id=app v0.1.0 target="app"}: cargo::core::compiler::fingerprint:     dirty: TargetConfigurationChanged"#,
+        r#"    0.020000000s  INFO prepare_target{force=false package_id=libz-sys v1.1.23 target="build-script-build"}: cargo::core::compiler::fingerprint:     dirty: EnvVarChanged { name: "CC", old_value: Some("gcc"), new_value: None }"#,
+        r#"    0.030000000s  INFO prepare_target{force=false package_id=lib-a v0.1.0 target="lib_a"}: cargo::core::compiler::fingerprint:     dirty: ProfileConfigurationChanged"#,
+        r#"    0.040000000s  INFO prepare_target{force=false package_id=rusqlite v0.31.0 target="rusqlite"}: cargo::core::compiler::fingerprint:     dirty: UnitDependencyInfoChanged { old_name: "libz_sys", old_fingerprint: 1, new_name: "libz_sys", new_fingerprint: 2 }"#,
+    ]);
+
+    for format in ["text", "json"] {
+        let run = || {
+            let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+            cmd.arg("--input").arg(&log).arg("--format").arg(format);
+            cmd.assert().success().get_output().stdout.clone()
+        };
+        assert_eq!(run(), run(), "Two {format} reports of one log differ");
+    }
+}
+
+#[test]
+fn cli_json_tree_keeps_only_the_top_root_causes() {
+    let (_logs, log) = write_log(&[
+        r#"    0.010000000s  INFO prepare_target{force=false package_id=app v0.1.0 target="app"}: cargo::core::compiler::fingerprint:     dirty: TargetConfigurationChanged"#,
+        r#"    0.020000000s  INFO prepare_target{force=false package_id=libz-sys v1.1.23 target="build-script-build"}: cargo::core::compiler::fingerprint:     dirty: EnvVarChanged { name: "CC", old_value: Some("gcc"), new_value: None }"#,
+        r#"    0.030000000s  INFO prepare_target{force=false package_id=rusqlite v0.31.0 target="rusqlite"}: cargo::core::compiler::fingerprint:     dirty: UnitDependencyInfoChanged { old_name: "libz_sys", old_fingerprint: 1, new_name: "libz_sys", new_fingerprint: 2 }"#,
+    ]);
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--input")
+        .arg(&log)
+        .arg("--format")
+        .arg("json-tree")
+        .arg("--top")
+        .arg("1");
+
+    let output = cmd.assert().success();
+    let trees: serde_json::Value = serde_json::from_slice(&output.get_output().stdout).unwrap();
+    assert_eq!(trees.as_array().map(Vec::len), Some(1), "Got: {trees}");
+    assert_eq!(
+        trees[0]["node"]["package"]["package_id"],
+        "libz-sys v1.1.23"
+    );
+    assert_eq!(
+        trees[0]["cascades"][0]["node"]["package"]["package_id"],
+        "rusqlite v0.31.0"
+    );
+}
+
+#[test]
+fn cli_group_by_reason_prints_a_header_per_reason_kind() {
+    let (_logs, log) = write_log(&[
+        r#"    0.010000000s  INFO prepare_target{force=false package_id=libz-sys v1.1.23 target="build-script-build"}: cargo::core::compiler::fingerprint:     dirty: EnvVarChanged { name: "CC", old_value: Some("gcc"), new_value: None }"#,
+        r#"    0.020000000s  INFO prepare_target{force=false package_id=cc v1.0.0 target="cc"}: cargo::core::compiler::fingerprint:     dirty: EnvVarChanged { name: "CC", old_value: Some("gcc"), new_value: None }"#,
+        r#"    0.030000000s  INFO prepare_target{force=false package_id=app v0.1.0 target="app"}: cargo::core::compiler::fingerprint:     dirty: ProfileConfigurationChanged"#,
+        r#"    0.040000000s  INFO prepare_target{force=false package_id=rusqlite v0.31.0 target="rusqlite"}: cargo::core::compiler::fingerprint:     dirty: UnitDependencyInfoChanged { old_name: "libz_sys", old_fingerprint: 1, new_name: "libz_sys", new_fingerprint: 2 }"#,
+    ]);
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--input").arg(&log).arg("--group-by").arg("reason");
+
+    let output = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    for header in ["env (2 units):", "dep (1 unit):", "profile (1 unit):"] {
+        assert!(
+            stdout.contains(header),
+            "Expected header `{header}`, got: {stdout}"
+        );
+    }
+    assert!(
+        !stdout.contains("root cause"),
+        "Grouping by reason replaces the root cause listing, got: {stdout}"
+    );
+}
+
+#[test]
+fn cli_notes_when_the_log_looks_like_a_first_build() {
+    let lines: Vec<_> = (0..12)
+        .map(|idx| {
+            format!(
+                "    0.010000000s  INFO prepare_target{{force=false package_id=crate-{idx} v0.1.0 \
+                 target=\"crate_{idx}\"}}: cargo::core::compiler::fingerprint:     dirty: \
+                 FsStatusOutdated(StaleItem(MissingFile(\"/t/debug/deps/libcrate_{idx}.rlib\")))"
+            )
+        })
+        .collect();
+    let (_logs, log) = write_log(&lines);
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--input").arg(&log);
+
+    let output = cmd.assert().success();
+    let stderr = String::from_utf8_lossy(&output.get_output().stderr);
+    assert!(
+        stderr.contains("This looks like a first/clean build"),
+        "Expected a first build notice, got: {stderr}"
+    );
+}
+
+#[test]
+fn cli_min_severity_hides_expected_rebuilds() {
+    let (_logs, log) = write_log(&[
+        r#"    0.010000000s  INFO prepare_target{force=false package_id=app v0.1.0 target="app"}: cargo::core::compiler::fingerprint:     dirty: FsStatusOutdated(StaleItem(ChangedFile { reference: "/t/dep-app", reference_mtime: FileTime { seconds: 1, nanos: 0 }, stale: "/p/src/main.rs", stale_mtime: FileTime { seconds: 2, nanos: 0 } }))"#,
+        r#"    0.020000000s  INFO prepare_target{force=false package_id=libz-sys v1.1.23 target="build-script-build"}: cargo::core::compiler::fingerprint:     dirty: EnvVarChanged { name: "CARGO_TARGET_DIR", old_value: Some("target"), new_value: None }"#,
+    ]);
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--input")
+        .arg(&log)
+        .arg("--min-severity")
+        .arg("warn");
+
+    let output = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    assert!(
+        stdout.contains("1 root cause:")
+            && stdout.contains("libz-sys [build-script-build] env:CARGO_TARGET_DIR")
+            && stdout.contains("(warn)")
+            && !stdout.contains("main.rs"),
+        "Expected only the environment change, got: {stdout}"
+    );
+}
+
+#[test]
+fn cli_expect_clean_fails_on_environment_churn() {
+    let project = TempDir::new().unwrap();
+    let root = project.path().canonicalize().unwrap();
+    let edit = format!(
+        r#"    0.010000000s  INFO prepare_target{{force=false package_id=app v0.1.0 target="app"}}: cargo::core::compiler::fingerprint:     dirty: FsStatusOutdated(StaleItem(ChangedFile {{ reference: "/t/dep-app", reference_mtime: FileTime {{ seconds: 1, nanos: 0 }}, stale: "{}/src/main.rs", stale_mtime: FileTime {{ seconds: 2, nanos: 0 }} }}))"#,
+        root.display()
+    );
+    let cc = r#"    0.020000000s  INFO prepare_target{force=false package_id=libz-sys v1.1.23 target="build-script-build"}: cargo::core::compiler::fingerprint:     dirty: EnvVarChanged { name: "CC", old_value: Some("gcc"), new_value: None }"#;
+    let (_clean_logs, clean) = write_log(&[edit.as_str()]);
+    let (_dirty_logs, dirty) = write_log(&[edit.as_str(), cc]);
+
+    let expect_clean = |log: &Path| {
+        let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+        cmd.arg("--path")
+            .arg(project.path())
+            .arg("--input")
+            .arg(log)
+            .arg("--expect-clean");
+        cmd.assert()
+    };
+
+    expect_clean(&clean).success();
+    let output = expect_clean(&dirty).code(2);
+    let stderr = String::from_utf8_lossy(&output.get_output().stderr);
+    assert!(
+        stderr.contains("unexpected rebuild trigger: env:CC") && !stderr.contains("main.rs"),
+        "Expected only the CC change to be rejected, got: {stderr}"
+    );
+}
+
+#[test]
+fn cli_double_run_reports_nothing_for_an_unchanged_project() {
+    let project = create_binary_project();
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--path").arg(project.path()).arg("--double-run");
+
+    let output = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    assert!(
+        stdout.contains("No rebuild triggers detected"),
+        "Expected the first build to be filtered out, got: {stdout}"
+    );
+}
+
+#[test]
+fn cli_reports_a_missing_cargo_binary() {
+    let project = create_binary_project();
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--path")
+        .arg(project.path())
+        .arg("--cargo-bin")
+        .arg("/nonexistent/cargo");
+
+    let output = cmd.assert().failure();
+    let stderr = String::from_utf8_lossy(&output.get_output().stderr);
+    assert!(
+        stderr.contains("cargo not found at `/nonexistent/cargo`")
+            && stderr.contains("--cargo-bin"),
+        "Expected a missing cargo error, got: {stderr}"
+    );
+}
+
+#[test]
+fn cli_no_suggestions_prints_only_what_changed() {
+    let (_logs, log) = write_log(&[
+        r#"    0.020000000s  INFO prepare_target{force=false package_id=libz-sys v1.1.23 target="build-script-build"}: cargo::core::compiler::fingerprint:     dirty: EnvVarChanged { name: "CC", old_value: Some("gcc"), new_value: None }"#,
+    ]);
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--input").arg(&log).arg("--no-suggestions");
+
+    let output = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    assert!(
+        stdout.contains("Environment variable CC changed") && !stdout.contains("Suggestions"),
+        "Expected the explanation without suggestions, got: {stdout}"
+    );
+}
+
+#[test]
+fn cli_compact_json_is_one_line_with_the_same_content() {
+    let (_logs, log) = write_log(&[
+        r#"    0.010000000s  INFO prepare_target{force=false package_id=app v0.1.0 target="app"}: cargo::core::compiler::fingerprint:     dirty: TargetConfigurationChanged"#,
+        r#"    0.020000000s  INFO prepare_target{force=false package_id=libz-sys v1.1.23 target="build-script-build"}: cargo::core::compiler::fingerprint:     dirty: EnvVarChanged { name: "CC", old_value: Some("gcc"), new_value: None }"#,
+    ]);
+
+    let run = |compact: bool| {
+        let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+        cmd.arg("--input").arg(&log).arg("--json");
+        if compact {
+            cmd.arg("--compact");
+        }
+        String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap()
+    };
+    let pretty = run(false);
+    let compact = run(true);
+
+    assert!(
+        !compact.trim_end().contains('\n'),
+        "Expected a single line, got: {compact}"
+    );
+    let pretty: serde_json::Value = serde_json::from_str(&pretty).unwrap();
+    let compact: serde_json::Value = serde_json::from_str(&compact).unwrap();
+    assert_eq!(compact, pretty);
+}
+
+#[test]
+fn cli_elevates_a_build_script_trigger() {
+    let (_logs, log) = write_log(&[
+        r#"    0.010000000s  INFO prepare_target{force=false package_id=app v0.1.0 target="app"}: cargo::core::compiler::fingerprint:     dirty: ProfileConfigurationChanged"#,
+        r#"    0.020000000s  INFO prepare_target{force=false package_id=libz-sys v1.1.23 target="build-script-build"}: cargo::core::compiler::fingerprint:     dirty: FsStatusOutdated(StaleItem(ChangedFile { reference: "/t/dep-build-script", reference_mtime: FileTime { seconds: 1, nanos: 0 }, stale: "/p/libz-sys/build.rs", stale_mtime: FileTime { seconds: 2, nanos: 0 } }))"#,
+        r#"    0.030000000s  INFO prepare_target{force=false package_id=rusqlite v0.31.0 target="rusqlite"}: cargo::core::compiler::fingerprint:     dirty: UnitDependencyInfoChanged { old_name: "libz_sys", old_fingerprint: 1, new_name: "libz_sys", new_fingerprint: 2 }"#,
+    ]);
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--input").arg(&log);
+
+    let output = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    let trigger = stdout.find("build script trigger: /p/libz-sys/build.rs");
+    let profile = stdout.find("app [app]");
+    assert!(
+        trigger.is_some() && trigger < profile,
+        "Expected the build script trigger listed first, got: {stdout}"
+    );
+    assert!(
+        stdout.contains("  - rusqlite [rusqlite]"),
+        "Expected the downstream crate, got: {stdout}"
+    );
+}
+
+#[test]
+fn cli_rejects_json_with_another_format() {
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--input")
+        .arg("build.log")
+        .arg("--json")
+        .arg("--format")
+        .arg("csv");
+
+    let output = cmd.assert().failure();
+    let stderr = String::from_utf8_lossy(&output.get_output().stderr);
+    assert!(
+        stderr.contains("--json can only be combined with --format json or --format frequency"),
+        "Expected a usage error, got: {stderr}"
+    );
+}
+
+#[cfg(feature = "tui")]
+#[test]
+fn cli_tui_requires_a_terminal() {
+    let (_logs, log) = write_log(&[
+        r#"    0.010000000s  INFO prepare_target{force=false package_id=app v0.1.0 target="app"}: cargo::core::compiler::fingerprint:     dirty: ProfileConfigurationChanged"#,
+    ]);
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--input").arg(&log).arg("--tui");
+
+    let output = cmd.assert().failure();
+    let stderr = String::from_utf8_lossy(&output.get_output().stderr);
+    assert!(
+        stderr.contains("--tui needs an interactive terminal") && !stderr.contains("panicked"),
+        "Expected a terminal error, got: {stderr}"
+    );
+}
+
+#[test]
+fn cli_csv_honors_the_package_filter() {
+    let (_logs, log) = write_log(&[
+        r#"    0.010000000s  INFO prepare_target{force=false package_id=app v0.1.0 target="app"}: cargo::core::compiler::fingerprint:     dirty: ProfileConfigurationChanged"#,
+        r#"    0.020000000s  INFO prepare_target{force=false package_id=lib-a v0.1.0 target="lib_a"}: cargo::core::compiler::fingerprint:     dirty: ProfileConfigurationChanged"#,
+    ]);
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--input")
+        .arg(&log)
+        .arg("--format")
+        .arg("csv")
+        .arg("--package")
+        .arg("app");
+
+    let output = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    assert!(
+        stdout.contains("app v0.1.0") && !stdout.contains("lib-a"),
+        "Expected only app in the CSV, got: {stdout}"
+    );
+}
+
+#[test]
+fn cli_frequency_honors_the_package_filters() {
+    let (_logs, log) = write_log(&[
+        r#"    0.010000000s  INFO prepare_target{force=false package_id=app v0.1.0 target="app"}: cargo::core::compiler::fingerprint:     dirty: ProfileConfigurationChanged"#,
+        r#"    0.020000000s  INFO prepare_target{force=false package_id=cc v1.0.0 target="cc"}: cargo::core::compiler::fingerprint:     dirty: EnvVarChanged { name: "CC", old_value: Some("gcc"), new_value: None }"#,
+    ]);
+
+    for filter in [["--package", "app"], ["--exclude", "cc"]] {
+        let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+        cmd.arg("--input")
+            .arg(&log)
+            .arg("--format")
+            .arg("frequency")
+            .args(filter);
+
+        let output = cmd.assert().success();
+        let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+        assert!(
+            stdout.contains("profile changed: 1 unit rebuilt")
+                && stdout.contains("app: 1 rebuild")
+                && !stdout.contains("CC")
+                && !stdout.contains("cc:"),
+            "Expected only app with {filter:?}, got: {stdout}"
+        );
+    }
+}
+
+#[test]
+fn cli_frequency_json_honors_the_package_filter() {
+    let (_logs, log) = write_log(&[
+        r#"    0.010000000s  INFO prepare_target{force=false package_id=app v0.1.0 target="app"}: cargo::core::compiler::fingerprint:     dirty: ProfileConfigurationChanged"#,
+        r#"    0.020000000s  INFO prepare_target{force=false package_id=cc v1.0.0 target="cc"}: cargo::core::compiler::fingerprint:     dirty: EnvVarChanged { name: "CC", old_value: Some("gcc"), new_value: None }"#,
+    ]);
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--input")
+        .arg(&log)
+        .arg("--format")
+        .arg("frequency")
+        .arg("--json")
+        .arg("--package")
+        .arg("app");
+
+    let output = cmd.assert().success();
+    let report: serde_json::Value = serde_json::from_slice(&output.get_output().stdout).unwrap();
+    let entries = report["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 1, "Got: {report}");
+    assert_eq!(entries[0]["reason_kind"], "profile");
+}
+
+#[test]
+fn cli_warns_about_an_edited_proc_macro() {
+    let workspace = create_workspace(&[
+        (
+            "derive-a",
+            "\n[lib]\nproc-macro = true",
+            "src/lib.rs",
+            "#[proc_macro]\npub fn answer(_: proc_macro::TokenStream) -> proc_macro::TokenStream \
+             { \"42\".parse().unwrap() }",
+        ),
+        (
+            "app",
+            "derive-a = { path = \"../derive-a\" }",
+            "src/main.rs",
+            "fn main() { println!(\"{}\", derive_a::answer!()); }",
+        ),
+    ]);
+    Command::new("cargo")
+        .arg("check")
+        .current_dir(workspace.path())
+        .assert()
+        .success();
+    fs::write(
+        workspace.path().join("derive-a/src/lib.rs"),
+        "#[proc_macro]\npub fn answer(_: proc_macro::TokenStream) -> proc_macro::TokenStream { \
+         \"43\".parse().unwrap() }",
+    )
+    .unwrap();
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--path").arg(workspace.path());
+
+    let output = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    assert!(
+        stdout.contains("this is a proc-macro; rebuilding it invalidates all dependents"),
+        "Expected a proc-macro warning, got: {stdout}"
+    );
+}
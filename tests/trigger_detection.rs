@@ -4,6 +4,7 @@ use std::{
 };
 
 use assert_cmd::{cargo, prelude::*};
+use cargo_frequent::{parse_package_target, parse_rebuild_entry};
 use tempfile::TempDir;
 
 fn create_test_project(name: &str) -> TempDir {
@@ -106,6 +107,101 @@ fn detects_rebuilds_when_environment_variables_change() {
     );
 }
 
+#[test]
+fn ascii_flag_replaces_emoji_with_tags() {
+    let project = create_test_project("ascii-test");
+
+    let mut cmd1 = Command::new("cargo");
+    cmd1.arg("build")
+        .current_dir(project.path())
+        .env_remove("CUSTOM_VAR");
+    let _ = cmd1.assert();
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--path")
+        .arg(project.path())
+        .arg("--command")
+        .arg("build")
+        .arg("--ascii")
+        .env("CUSTOM_VAR", "test_value");
+
+    let output = cmd.assert().success();
+    let stdout = &output.get_output().stdout;
+    let text = String::from_utf8_lossy(stdout);
+    assert!(
+        text.contains("[ENV] Environment variable CUSTOM_VAR changed"),
+        "Expected an [ENV] tag, got: {text}"
+    );
+    assert!(stdout.is_ascii(), "Expected ASCII-only output, got: {text}");
+}
+
+#[test]
+fn fail_on_env_exits_with_failure() {
+    let project = create_test_project("fail-on-test");
+
+    let mut cmd1 = Command::new("cargo");
+    cmd1.arg("build")
+        .current_dir(project.path())
+        .env_remove("CUSTOM_VAR");
+    let _ = cmd1.assert();
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--path")
+        .arg(project.path())
+        .arg("--command")
+        .arg("build")
+        .arg("--fail-on")
+        .arg("env")
+        .env("CUSTOM_VAR", "test_value");
+
+    let output = cmd.assert().code(2);
+    let stderr = String::from_utf8_lossy(&output.get_output().stderr);
+    assert!(
+        stderr.contains("rebuild triggered by env:CUSTOM_VAR"),
+        "Expected the triggering reason on stderr, got: {stderr}"
+    );
+}
+
+#[test]
+fn env_option_sets_variable_for_cargo() {
+    let project = create_test_project("env-option-test");
+
+    let mut cmd1 = Command::new("cargo");
+    cmd1.arg("build")
+        .current_dir(project.path())
+        .env_remove("CUSTOM_VAR");
+    let _ = cmd1.assert();
+
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--path")
+        .arg(project.path())
+        .arg("--command")
+        .arg("build")
+        .arg("--env")
+        .arg("CUSTOM_VAR=x")
+        .env_remove("CUSTOM_VAR");
+
+    let output = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    assert!(
+        stdout.contains("env:CUSTOM_VAR"),
+        "Expected the --env variable to reach the build, got: {stdout}"
+    );
+}
+
+#[test]
+fn env_option_requires_an_equals_sign() {
+    let mut cmd = Command::new(cargo::cargo_bin!("cargo-frequent"));
+    cmd.arg("--env").arg("CUSTOM_VAR");
+
+    let output = cmd.assert().failure();
+    let stderr = String::from_utf8_lossy(&output.get_output().stderr);
+    assert!(
+        stderr.contains("expected KEY=VALUE"),
+        "Expected a usage error, got: {stderr}"
+    );
+}
+
 #[test]
 fn detects_rebuilds_when_c_compiler_environment_changes() {
     let project = create_test_project("cc-test");
@@ -181,3 +277,14 @@ fn main() {
         "Expected stdout to contain 'file:' and 'main.rs', got: {stdout}"
     );
 }
+
+#[test]
+fn public_parser_extracts_package_and_target() {
+    let line = r#"    0.215830470s  INFO prepare_target{force=false package_id=openssl-sys v0.9.109 (registry+https://github.com/rust-lang/crates.io-index) target="build-script-main"}: cargo::core::compiler::fingerprint:     dirty: EnvVarChanged { name: "OPENSSL_DIR", old_value: None, new_value: Some("/opt/openssl") }"#;
+
+    let package = parse_package_target(line);
+
+    assert_eq!(package.package_id, "openssl-sys v0.9.109");
+    assert_eq!(package.target.as_deref(), Some("build-script-main"));
+    assert_eq!(parse_rebuild_entry(line)[0].package, package);
+}